@@ -1,33 +1,365 @@
-use std::thread;
+use tigris_core::features::{api::RunActionRequest, utils::send_notification};
 
-use tigris_core::features::api::RunActionRequest;
+use crate::{
+    archives::archive_page,
+    bookmarks::{now, Bookmark, ReadState},
+    clipboard::{copy_text, LinkFormat},
+    favicons::write_favicon,
+    integrity::check_db,
+    launch::open_group,
+    pages::fetch_page,
+    paths::{get_archive_path, get_declared_path},
+    profiles::switch_profile,
+    storage::open_store,
+    store::{sync_db, BookmarkStore},
+};
 
-use crate::bookmarks::{get_db, Bookmark};
-
-pub fn handle_actions(request: RunActionRequest) {
+pub async fn handle_actions(request: RunActionRequest, store: &dyn BookmarkStore) {
     let action = request.action;
+    let dirs = store.dirs();
 
     match action.as_str() {
+        "open-bookmark" => {
+            let args = request.args;
+            let bookmark_id = args.first().unwrap().to_owned();
+
+            // Results of the all keyword open bookmarks of other profiles
+            match args.get(1) {
+                Some(profile) => match open_store(&dirs.with_profile(profile)) {
+                    Ok(profile_store) => open_bookmark(profile_store.as_ref(), &bookmark_id),
+                    Err(error) => send_notification("Error Opening Bookmarks", &error),
+                },
+                None => open_bookmark(store, &bookmark_id),
+            }
+        }
+        "add-bookmark" => {
+            let args = request.args;
+            let link = args.first().unwrap().to_owned();
+
+            if store.list().iter().any(|bookmark| bookmark.link == link) {
+                send_notification(
+                    "Already Bookmarked",
+                    &format!("{link} is already a bookmark"),
+                );
+                return;
+            }
+
+            // Without a typed name the page title is used
+            let name = match args.get(1).filter(|name| !name.trim().is_empty()) {
+                Some(name) => name.trim().to_owned(),
+                None => fetch_page(&link)
+                    .await
+                    .ok()
+                    .and_then(|page| page.title)
+                    .unwrap_or(link.to_owned()),
+            };
+
+            let bookmark = Bookmark::new(&name, &link);
+
+            write_favicon(dirs, &bookmark).await;
+            store.insert(&bookmark);
+
+            send_notification("Success", &format!("{name} added successfully"));
+        }
+        "open-read-later" | "archive-read-later" => {
+            let args = request.args;
+            let item_id = args.first().unwrap().to_owned();
+            let mut db = store.get_db();
+
+            if let Some(item) = db.read_later.iter_mut().find(|item| item.id == item_id) {
+                if action == "open-read-later" {
+                    if let Err(error) = open::that(&item.link) {
+                        send_notification("Error Opening Link", &error.to_string());
+                        return;
+                    }
+
+                    item.set_state(ReadState::Read);
+                } else {
+                    item.set_state(ReadState::Archived);
+                }
+            }
+
+            store.write_db(&db);
+        }
         "open-group" => {
             let args = request.args;
-            let group_id = args.get(0).unwrap().parse::<usize>().unwrap();
-            let db = get_db();
+            let group_id = args.first().unwrap().to_owned();
+            let mut db = store.get_db();
 
-            let group = db.groups.iter().find(|group| group.id == group_id).unwrap();
+            if let Err(error) = open_group(&mut db, &group_id, None) {
+                send_notification("Error Opening Group", &error);
+                return;
+            }
+
+            store.write_db(&db);
+        }
+        "add-to-group" | "remove-from-group" => {
+            let args = request.args;
+            let group_id = args.first().unwrap().to_owned();
+            let bookmark_id = args.get(1).unwrap().to_owned();
+            let mut db = store.get_db();
 
-            let bookmarks = db
+            let bookmark_name = match db
                 .bookmarks
                 .iter()
-                .filter(|bookmark| group.bookmarks_ids.contains(&bookmark.id))
-                .map(|bookmark| bookmark.to_owned())
-                .collect::<Vec<Bookmark>>();
+                .find(|bookmark| bookmark.id == bookmark_id)
+            {
+                Some(bookmark) => bookmark.name.to_owned(),
+                None => {
+                    send_notification("Bookmark Not Found", "The bookmark doesn't exist anymore");
+                    return;
+                }
+            };
+
+            let group = match db.groups.iter_mut().find(|group| group.id == group_id) {
+                Some(group) => group,
+                None => {
+                    send_notification("Group Not Found", "The group doesn't exist anymore");
+                    return;
+                }
+            };
+
+            let message = if action == "add-to-group" {
+                if !group.bookmarks_ids.contains(&bookmark_id) {
+                    group.bookmarks_ids.push(bookmark_id);
+                }
+
+                format!("{bookmark_name} added to {}", group.name)
+            } else {
+                group.bookmarks_ids.retain(|id| id != &bookmark_id);
+
+                format!("{bookmark_name} removed from {}", group.name)
+            };
+
+            group.updated_at = now();
+            store.write_db(&db);
+
+            send_notification("Success", &message);
+        }
+        "archive-bookmark" => {
+            let args = request.args;
+            let bookmark_id = args.first().unwrap().to_owned();
+
+            let mut bookmark = match store.get(&bookmark_id) {
+                Some(bookmark) => bookmark,
+                None => {
+                    send_notification("Bookmark Not Found", "The bookmark doesn't exist anymore");
+                    return;
+                }
+            };
+
+            match archive_page(dirs, &bookmark).await {
+                Ok(_) => {
+                    bookmark.archived_at = Some(now());
+                    store.update(&bookmark);
+
+                    send_notification("Success", &format!("{} archived", bookmark.name));
+                }
+                Err(error) => send_notification("Error Archiving Page", &error),
+            }
+        }
+        "copy-bookmark" => {
+            let args = request.args;
+            let bookmark_id = args.first().unwrap().to_owned();
+            let format = args
+                .get(1)
+                .and_then(|format| LinkFormat::from_name(format))
+                .unwrap_or(LinkFormat::Link);
+
+            let bookmark = match store.get(&bookmark_id) {
+                Some(bookmark) => bookmark,
+                None => {
+                    send_notification("Bookmark Not Found", "The bookmark doesn't exist anymore");
+                    return;
+                }
+            };
+
+            // Clipboard managers would keep it in their history
+            if bookmark.private {
+                send_notification("Not Copied", "Private bookmarks aren't copied");
+                return;
+            }
+
+            let text = format.format(&bookmark);
+
+            match copy_text(&text) {
+                Ok(_) => send_notification("Copied", &text),
+                Err(error) => send_notification("Error Copying", &error),
+            }
+        }
+        "open-archive" => {
+            let args = request.args;
+            let bookmark_id = args.first().unwrap().to_owned();
+            let archive_path = get_archive_path(dirs, &bookmark_id);
+
+            if archive_path.exists() {
+                open::that(archive_path).expect("Error opening archived copy");
+            } else {
+                send_notification("Archive Not Found", "Archive the bookmark again");
+            }
+        }
+        "restore-bookmark" => {
+            let args = request.args;
+            let bookmark_id = args.first().unwrap().to_owned();
+            let mut db = store.get_db();
+
+            if let Some(bookmark) = db.restore_bookmark(&bookmark_id) {
+                store.write_db(&db);
+                send_notification(
+                    "Success",
+                    &format!("{} restored successfully", bookmark.name),
+                );
+            }
+        }
+        "restore-group" => {
+            let args = request.args;
+            let group_id = args.first().unwrap().to_owned();
+            let mut db = store.get_db();
+
+            if let Some(group) = db.restore_group(&group_id) {
+                store.write_db(&db);
+                send_notification("Success", &format!("{} restored successfully", group.name));
+            }
+        }
+        "empty-trash" => {
+            let mut db = store.get_db();
+
+            db.empty_trash(dirs);
+            store.write_db(&db);
+
+            send_notification("Success", "Trash emptied successfully");
+        }
+        "check-db" => {
+            let mut db = store.get_db();
+            let report = check_db(dirs, &mut db);
+
+            store.write_db(&db);
 
-            for bookmark in bookmarks {
-                thread::spawn(move || {
-                    open::that(&bookmark.link).expect("Error opening bookmark");
-                });
+            send_notification("Database Checked", &report.summary());
+        }
+        "open-declared-file" => {
+            open::that(get_declared_path(dirs)).expect("Error opening bookmarks.toml");
+        }
+        "switch-profile" => {
+            let args = request.args;
+            let profile = args.first().unwrap().to_owned();
+
+            match switch_profile(&dirs.data_dir, &profile) {
+                Ok(()) => {
+                    send_notification("Success", &format!("Switched to the {profile} profile"))
+                }
+                Err(error) => send_notification("Invalid Profile", &error),
             }
         }
+        "sync-now" => match sync_db(store).await {
+            Ok(true) => send_notification("Success", "Bookmarks synced with the remote changes"),
+            Ok(false) => send_notification("Success", "Bookmarks are up to date"),
+            Err(error) => send_notification("Sync Failed", &error),
+        },
         _ => {}
     }
 }
+
+fn open_bookmark(store: &dyn BookmarkStore, bookmark_id: &str) {
+    let mut bookmark = match store.get(bookmark_id) {
+        Some(bookmark) => bookmark,
+        None => {
+            send_notification("Bookmark Not Found", "The bookmark doesn't exist anymore");
+            return;
+        }
+    };
+
+    if let Err(error) = open::that(&bookmark.link) {
+        send_notification("Error Opening Bookmark", &error.to_string());
+        return;
+    }
+
+    bookmark.mark_opened();
+    store.update(&bookmark);
+}
+
+#[cfg(test)]
+mod tests {
+    use tigris_core::features::api::RunActionRequest;
+
+    use super::handle_actions;
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB, Group, ReadLaterItem, ReadState},
+        paths::get_test_dirs,
+        store::{BookmarkStore, MemoryStore},
+    };
+
+    fn request(action: &str, args: &[&str]) -> RunActionRequest {
+        RunActionRequest {
+            action: action.to_owned(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn add_to_group_and_remove_from_group() {
+        let bookmark = Bookmark::new("Docs", "https://docs.rs");
+        let group = Group::new("Work", &vec![]);
+        let db = BookmarksDB {
+            bookmarks: vec![bookmark.to_owned()],
+            groups: vec![group.to_owned()],
+            ..Default::default()
+        };
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, db);
+
+        handle_actions(request("add-to-group", &[&group.id, &bookmark.id]), &store).await;
+        assert_eq!(
+            store.get_db().groups[0].bookmarks_ids,
+            vec![bookmark.id.to_owned()]
+        );
+
+        handle_actions(
+            request("remove-from-group", &[&group.id, &bookmark.id]),
+            &store,
+        )
+        .await;
+        assert!(store.get_db().groups[0].bookmarks_ids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn restore_and_empty_trash() {
+        let kept = Bookmark::new("Docs", "https://docs.rs");
+        let purged = Bookmark::new("Crates", "https://crates.io");
+        let mut db = BookmarksDB {
+            bookmarks: vec![kept.to_owned(), purged.to_owned()],
+            ..Default::default()
+        };
+        db.trash_bookmark(&kept.id);
+        db.trash_bookmark(&purged.id);
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, db);
+
+        handle_actions(request("restore-bookmark", &[&kept.id]), &store).await;
+        handle_actions(request("empty-trash", &[]), &store).await;
+
+        let db = store.get_db();
+        assert_eq!(db.bookmarks.len(), 1);
+        assert_eq!(db.bookmarks[0].id, kept.id);
+        assert!(db.trash.bookmarks.is_empty());
+        assert!(db
+            .tombstones
+            .iter()
+            .any(|tombstone| tombstone.id == purged.id));
+    }
+
+    #[tokio::test]
+    async fn archive_read_later_changes_the_state() {
+        let item = ReadLaterItem::new("Release notes", "https://example.com/notes", Some(3));
+        let db = BookmarksDB {
+            read_later: vec![item.to_owned()],
+            ..Default::default()
+        };
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, db);
+
+        handle_actions(request("archive-read-later", &[&item.id]), &store).await;
+
+        assert_eq!(store.get_db().read_later[0].state, ReadState::Archived);
+    }
+}
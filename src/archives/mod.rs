@@ -0,0 +1,474 @@
+use std::{
+    collections::HashMap, fs, future::Future, path::PathBuf, pin::Pin, sync::LazyLock,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use regex::{Captures, NoExpand, Regex};
+use reqwest::{header::CONTENT_TYPE, Client, Url};
+
+use crate::{
+    bookmarks::Bookmark,
+    import::decode_entities,
+    paths::{get_archive_path, get_archives_dir, Dirs},
+    settings::get_settings,
+    storage::StorageBackend,
+};
+
+const MAX_IMPORT_DEPTH: usize = 5;
+
+static LINK_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<link\b[^>]*>").unwrap());
+static STYLE_BLOCK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)(<style\b[^>]*>)(.*?)(</style>)").unwrap());
+static CSS_IMPORT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)@import\s+(?:url\(\s*['"]?([^'")]+?)['"]?\s*\)|['"]([^'"]+)['"])\s*([^;]*);"#)
+        .unwrap()
+});
+static CSS_URL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)url\(\s*['"]?([^'")]+?)['"]?\s*\)"#).unwrap());
+static SCRIPT_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<script\b([^>]*)>\s*</script>").unwrap());
+static SCRIPT_REMOVED_ATTRIBUTES_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)\s(src|integrity|crossorigin)\s*=\s*("[^"]*"|'[^']*'|[^\s>]+)"#).unwrap()
+});
+static IMAGE_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<img\b[^>]*>").unwrap());
+static SRC_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)\ssrc\s*=\s*("[^"]*"|'[^']*'|[^\s>]+)"#).unwrap());
+static SRCSET_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)\ssrcset\s*=\s*("[^"]*"|'[^']*')"#).unwrap());
+static ANCHOR_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<a\b[^>]*>").unwrap());
+static HREF_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)\shref\s*=\s*("[^"]*"|'[^']*'|[^\s>]+)"#).unwrap());
+static ATTRIBUTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)\s([a-z][a-z0-9-]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap()
+});
+
+struct Resource {
+    mime_type: String,
+    bytes: Vec<u8>,
+}
+
+pub async fn archive_page(dirs: &Dirs, bookmark: &Bookmark) -> Result<PathBuf, String> {
+    if get_settings(dirs).storage == StorageBackend::Encrypted {
+        return Err(String::from(
+            "Offline copies are saved unencrypted, so they're off while the storage is encrypted",
+        ));
+    }
+
+    let client = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|error| error.to_string())?;
+
+    let response = client
+        .get(&bookmark.link)
+        .send()
+        .await
+        .map_err(|error| error.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!("The page answered {}", response.status()));
+    }
+
+    // Redirects change what relative links point to
+    let page_url = response.url().to_owned();
+    let html = response.text().await.map_err(|error| error.to_string())?;
+
+    let html = inline_stylesheets(&client, &page_url, &html).await;
+    let html = inline_style_blocks(&client, &page_url, &html).await;
+    let html = inline_scripts(&client, &page_url, &html).await;
+    let html = inline_images(&client, &page_url, &html).await;
+    let html = absolutize_links(&page_url, &html);
+
+    let archives_dir = get_archives_dir(dirs);
+
+    if !archives_dir.exists() {
+        fs::create_dir_all(&archives_dir).map_err(|error| error.to_string())?;
+    }
+
+    let archive_path = get_archive_path(dirs, &bookmark.id);
+    let archive = format!("<!-- Archived from {page_url} -->\n{html}");
+
+    fs::write(&archive_path, archive).map_err(|error| error.to_string())?;
+
+    Ok(archive_path)
+}
+
+pub fn remove_archive(dirs: &Dirs, bookmark_id: &str) {
+    let archive_path = get_archive_path(dirs, bookmark_id);
+
+    if archive_path.exists() {
+        fs::remove_file(archive_path).expect("Error deleting archive");
+    }
+}
+
+async fn inline_stylesheets(client: &Client, page_url: &Url, html: &str) -> String {
+    let stylesheet_urls = LINK_TAG_REGEX
+        .find_iter(html)
+        .map(|tag| tag.as_str())
+        .filter(|tag| {
+            get_attribute(tag, "rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+            })
+        })
+        .filter_map(|tag| resolve_url(page_url, &get_attribute(tag, "href")?))
+        .collect::<Vec<Url>>();
+
+    let mut stylesheets = HashMap::<String, String>::new();
+
+    for (url, resource) in fetch_resources(client, &stylesheet_urls).await {
+        let css = String::from_utf8_lossy(&resource.bytes).to_string();
+
+        // Urls in a stylesheet are relative to the stylesheet, not the page
+        let stylesheet_url = Url::parse(&url).expect("Error parsing stylesheet url");
+        let css = inline_css(client, &stylesheet_url, &css, 0).await;
+
+        stylesheets.insert(url, css);
+    }
+
+    LINK_TAG_REGEX
+        .replace_all(html, |captures: &Captures| {
+            let tag = &captures[0];
+
+            get_attribute(tag, "href")
+                .and_then(|href| resolve_url(page_url, &href))
+                .and_then(|url| stylesheets.get(url.as_str()))
+                .map(|css| format!("<style>{css}</style>"))
+                .unwrap_or(tag.to_owned())
+        })
+        .to_string()
+}
+
+async fn inline_style_blocks(client: &Client, page_url: &Url, html: &str) -> String {
+    let mut inlined = String::new();
+    let mut last_end = 0;
+
+    for captures in STYLE_BLOCK_REGEX.captures_iter(html) {
+        let block = captures.get(0).unwrap();
+        let css = inline_css(client, page_url, &captures[2], 0).await;
+
+        inlined.push_str(&html[last_end..block.start()]);
+        inlined.push_str(&format!("{}{css}{}", &captures[1], &captures[3]));
+        last_end = block.end();
+    }
+
+    inlined.push_str(&html[last_end..]);
+    inlined
+}
+
+fn inline_css<'a>(
+    client: &'a Client,
+    base_url: &'a Url,
+    css: &'a str,
+    depth: usize,
+) -> Pin<Box<dyn Future<Output = String> + Send + 'a>> {
+    Box::pin(async move {
+        let css = match depth < MAX_IMPORT_DEPTH {
+            true => inline_css_imports(client, base_url, css, depth).await,
+            false => css.to_owned(),
+        };
+
+        inline_css_urls(client, base_url, &css).await
+    })
+}
+
+async fn inline_css_imports(client: &Client, base_url: &Url, css: &str, depth: usize) -> String {
+    let get_import_url = |captures: &Captures| {
+        let link = captures.get(1).or(captures.get(2))?.as_str();
+        resolve_url(base_url, link)
+    };
+
+    let import_urls = CSS_IMPORT_REGEX
+        .captures_iter(css)
+        .filter_map(|captures| get_import_url(&captures))
+        .collect::<Vec<Url>>();
+
+    let mut imports = HashMap::<String, String>::new();
+
+    for (url, resource) in fetch_resources(client, &import_urls).await {
+        let imported_css = String::from_utf8_lossy(&resource.bytes).to_string();
+        let stylesheet_url = Url::parse(&url).expect("Error parsing stylesheet url");
+        let imported_css = inline_css(client, &stylesheet_url, &imported_css, depth + 1).await;
+
+        imports.insert(url, imported_css);
+    }
+
+    CSS_IMPORT_REGEX
+        .replace_all(css, |captures: &Captures| {
+            let media = captures[3].trim();
+
+            get_import_url(captures)
+                .and_then(|url| imports.get(url.as_str()))
+                .map(|imported_css| match media.is_empty() {
+                    true => imported_css.to_owned(),
+                    false => format!("@media {media} {{{imported_css}}}"),
+                })
+                .unwrap_or(captures[0].to_owned())
+        })
+        .to_string()
+}
+
+async fn inline_css_urls(client: &Client, base_url: &Url, css: &str) -> String {
+    let urls = CSS_URL_REGEX
+        .captures_iter(css)
+        .filter_map(|captures| resolve_url(base_url, &captures[1]))
+        .collect::<Vec<Url>>();
+
+    let resources = fetch_resources(client, &urls).await;
+
+    CSS_URL_REGEX
+        .replace_all(css, |captures: &Captures| {
+            resolve_url(base_url, &captures[1])
+                .and_then(|url| resources.get(url.as_str()))
+                .map(|resource| format!("url(\"{}\")", get_data_url(resource)))
+                .unwrap_or(captures[0].to_owned())
+        })
+        .to_string()
+}
+
+async fn inline_scripts(client: &Client, page_url: &Url, html: &str) -> String {
+    let script_urls = SCRIPT_TAG_REGEX
+        .captures_iter(html)
+        .filter_map(|captures| resolve_url(page_url, &get_attribute(&captures[1], "src")?))
+        .collect::<Vec<Url>>();
+
+    let scripts = fetch_resources(client, &script_urls).await;
+
+    SCRIPT_TAG_REGEX
+        .replace_all(html, |captures: &Captures| {
+            get_attribute(&captures[1], "src")
+                .and_then(|src| resolve_url(page_url, &src))
+                .and_then(|url| scripts.get(url.as_str()))
+                .map(|resource| {
+                    // The inlined script no longer matches its hash and has nothing to load
+                    let attributes = SCRIPT_REMOVED_ATTRIBUTES_REGEX.replace_all(&captures[1], "");
+
+                    // A closing tag inside the script would end it early
+                    let script =
+                        String::from_utf8_lossy(&resource.bytes).replace("</script", "<\\/script");
+
+                    format!("<script{attributes}>{script}</script>")
+                })
+                .unwrap_or(captures[0].to_owned())
+        })
+        .to_string()
+}
+
+async fn inline_images(client: &Client, page_url: &Url, html: &str) -> String {
+    let image_urls = IMAGE_TAG_REGEX
+        .find_iter(html)
+        .filter_map(|tag| resolve_url(page_url, &get_attribute(tag.as_str(), "src")?))
+        .collect::<Vec<Url>>();
+
+    let images = fetch_resources(client, &image_urls).await;
+
+    let html = IMAGE_TAG_REGEX.replace_all(html, |captures: &Captures| {
+        let tag = &captures[0];
+
+        get_attribute(tag, "src")
+            .and_then(|src| resolve_url(page_url, &src))
+            .and_then(|url| images.get(url.as_str()))
+            .map(|resource| {
+                let src = format!(" src=\"{}\"", get_data_url(resource));
+
+                SRC_REGEX.replace(tag, NoExpand(&src)).to_string()
+            })
+            .unwrap_or(tag.to_owned())
+    });
+
+    // The browser would pick an image from srcset over the inlined src and load it from the site
+    SRCSET_REGEX.replace_all(&html, "").to_string()
+}
+
+fn absolutize_links(page_url: &Url, html: &str) -> String {
+    ANCHOR_TAG_REGEX
+        .replace_all(html, |captures: &Captures| {
+            let tag = &captures[0];
+
+            get_attribute(tag, "href")
+                .filter(|href| !href.starts_with('#'))
+                .and_then(|href| resolve_url(page_url, &href))
+                .map(|url| {
+                    let href = format!(" href=\"{url}\"");
+
+                    HREF_REGEX.replace(tag, NoExpand(&href)).to_string()
+                })
+                .unwrap_or(tag.to_owned())
+        })
+        .to_string()
+}
+
+async fn fetch_resources(client: &Client, urls: &Vec<Url>) -> HashMap<String, Resource> {
+    let mut unique_urls = urls.to_owned();
+    unique_urls.sort();
+    unique_urls.dedup();
+
+    let tasks = unique_urls
+        .into_iter()
+        .map(|url| {
+            let client = client.to_owned();
+
+            tokio::spawn(async move {
+                let resource = fetch_resource(&client, &url).await;
+                (url, resource)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut resources = HashMap::<String, Resource>::new();
+
+    for task in tasks {
+        if let Ok((url, Some(resource))) = task.await {
+            resources.insert(url.to_string(), resource);
+        }
+    }
+
+    resources
+}
+
+async fn fetch_resource(client: &Client, url: &Url) -> Option<Resource> {
+    let response = client.get(url.to_owned()).send().await.ok()?;
+
+    if !response.status().is_success() {
+        return None;
+    }
+
+    let mime_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime_type| mime_type.trim().to_owned())
+        .unwrap_or(String::from("application/octet-stream"));
+
+    let bytes = response.bytes().await.ok()?.to_vec();
+
+    Some(Resource { mime_type, bytes })
+}
+
+fn resolve_url(base_url: &Url, link: &str) -> Option<Url> {
+    let url = base_url.join(&decode_entities(link.trim())).ok()?;
+
+    match url.scheme() {
+        "http" | "https" => Some(url),
+        _ => None,
+    }
+}
+
+fn get_attribute(tag: &str, name: &str) -> Option<String> {
+    let captures = ATTRIBUTE_REGEX
+        .captures_iter(tag)
+        .find(|captures| captures[1].eq_ignore_ascii_case(name))?;
+
+    captures
+        .get(2)
+        .or(captures.get(3))
+        .or(captures.get(4))
+        .map(|value| value.as_str().to_owned())
+}
+
+fn get_data_url(resource: &Resource) -> String {
+    format!(
+        "data:{};base64,{}",
+        resource.mime_type,
+        STANDARD.encode(&resource.bytes)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::archive_page;
+    use crate::{bookmarks::Bookmark, paths::get_test_dirs};
+
+    fn serve(files: Vec<(&'static str, &'static str, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}{}", listener.local_addr().unwrap(), files[0].0);
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+                let response = match files.iter().find(|(file_path, _, _)| *file_path == path) {
+                    Some((_, mime_type, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {mime_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    ),
+                    None => String::from(
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    ),
+                };
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn inlines_the_resources_of_the_page() {
+        let link = serve(vec![
+            (
+                "/page/index.html",
+                "text/html",
+                r#"<html><head><link rel="stylesheet" href="style.css"><script src="/app.js" integrity="sha-1"></script></head><body><img src="logo.png" srcset="logo@2x.png 2x"><a href="about.html">About</a></body></html>"#,
+            ),
+            (
+                "/page/style.css",
+                "text/css",
+                r#"@import url("css/base.css") screen; body { background: url(bg.png); }"#,
+            ),
+            (
+                "/page/css/base.css",
+                "text/css",
+                r#"@font-face { src: url("font.woff"); }"#,
+            ),
+            ("/page/css/font.woff", "font/woff", "font"),
+            ("/page/bg.png", "image/png", "background"),
+            ("/page/logo.png", "image/png", "logo"),
+            ("/app.js", "text/javascript", "console.log('</script>')"),
+        ]);
+
+        let dirs = get_test_dirs();
+        let bookmark = Bookmark::new("Page", &link);
+        let archive_path = archive_page(&dirs, &bookmark).await.unwrap();
+        let archive = fs::read_to_string(archive_path).unwrap();
+
+        assert!(!archive.contains("<link"));
+        assert!(!archive.contains("@import"));
+        assert!(archive.contains("@media screen {@font-face { src: url(\"data:font/woff;base64,"));
+        assert!(archive.contains("url(\"data:image/png;base64,"));
+        assert!(archive.contains("<script>console.log('<\\/script>')</script>"));
+        assert!(archive.contains("<img src=\"data:image/png;base64,"));
+        assert!(!archive.contains("srcset"));
+        assert!(archive.contains(&format!(
+            "href=\"{}\"",
+            link.replace("index.html", "about.html")
+        )));
+    }
+}
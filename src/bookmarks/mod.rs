@@ -1,103 +1,862 @@
-use std::fs;
+use std::{
+    cmp::Reverse,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
 
-use crate::paths::{get_config_dir, get_db_path};
+use crate::{archives::remove_archive, favicons::remove_favicon, paths::Dirs, rules::Rule};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+pub const DB_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BookmarksDB {
+    #[serde(default)]
+    pub version: u32,
     pub bookmarks: Vec<Bookmark>,
     pub groups: Vec<Group>,
+    #[serde(default)]
+    pub trash: Trash,
+    #[serde(default)]
+    pub tombstones: Vec<Tombstone>,
+    #[serde(default)]
+    pub read_later: Vec<ReadLaterItem>,
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Trash {
+    pub bookmarks: Vec<TrashedBookmark>,
+    pub groups: Vec<TrashedGroup>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashedBookmark {
+    pub bookmark: Bookmark,
+    #[serde(deserialize_with = "deserialize_ids")]
+    pub groups_ids: Vec<String>,
+    pub deleted_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashedGroup {
+    pub group: Group,
+    pub deleted_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tombstone {
+    pub id: String,
+    pub deleted_at: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Bookmark {
-    pub id: usize,
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
     pub name: String,
     pub link: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub declared: bool,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    #[serde(default)]
+    pub last_opened_at: Option<u64>,
+    #[serde(default)]
+    pub open_count: usize,
+    #[serde(default)]
+    pub archived_at: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Group {
-    pub id: usize,
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
     pub name: String,
-    pub bookmarks_ids: Vec<usize>,
+    #[serde(deserialize_with = "deserialize_ids")]
+    pub bookmarks_ids: Vec<String>,
+    #[serde(default)]
+    pub groups_ids: Vec<String>,
+    #[serde(default)]
+    pub rule: Option<String>,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub launch: LaunchOptions,
+    #[serde(default)]
+    pub declared: bool,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    #[serde(default)]
+    pub last_opened_at: Option<u64>,
+    #[serde(default)]
+    pub open_count: usize,
 }
 
-pub fn get_db() -> BookmarksDB {
-    let config_dir = get_config_dir();
-    let db_path = get_db_path();
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    #[serde(default)]
+    pub delay_ms: u64,
+    #[serde(default)]
+    pub max_tabs: Option<usize>,
+    #[serde(default)]
+    pub confirm_above: Option<usize>,
+    #[serde(default)]
+    pub browser: Option<String>,
+}
 
-    if !config_dir.exists() {
-        fs::create_dir_all(&config_dir).expect("Error creating bookmarks directory");
-    }
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReadLaterItem {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    #[serde(default)]
+    pub state: ReadState,
+    #[serde(default)]
+    pub reading_minutes: Option<u32>,
+    pub added_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    #[serde(default)]
+    pub read_at: Option<u64>,
+}
 
-    if !db_path.exists() {
-        let db = BookmarksDB {
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadState {
+    #[default]
+    Unread,
+    Read,
+    Archived,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortMode {
+    Alphabetical,
+    Newest,
+    RecentlyOpened,
+    MostOpened,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+impl Default for BookmarksDB {
+    fn default() -> Self {
+        Self {
+            version: DB_VERSION,
             bookmarks: vec![],
             groups: vec![],
+            trash: Trash::default(),
+            tombstones: vec![],
+            read_later: vec![],
+            warnings: vec![],
+        }
+    }
+}
+
+pub fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+pub fn is_id(text: &str) -> bool {
+    Uuid::parse_str(text).is_ok()
+}
+
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(id) => Ok(id),
+        value => Ok(value.to_string()),
+    }
+}
+
+fn deserialize_ids<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let values = Vec::<Value>::deserialize(deserializer)?;
+
+    Ok(values
+        .into_iter()
+        .map(|value| match value {
+            Value::String(id) => id,
+            value => value.to_string(),
+        })
+        .collect())
+}
+
+impl BookmarksDB {
+    pub fn migrate(&mut self) -> Option<Vec<(String, String)>> {
+        if self.version >= DB_VERSION {
+            return None;
+        }
+
+        let mut bookmark_ids = vec![];
+
+        if self.version < 1 {
+            bookmark_ids = self.migrate_ids();
+        }
+
+        self.version = DB_VERSION;
+
+        Some(bookmark_ids)
+    }
+
+    fn migrate_ids(&mut self) -> Vec<(String, String)> {
+        let mut bookmark_ids = Vec::<(String, String)>::new();
+        let mut group_ids = Vec::<(String, String)>::new();
+
+        let bookmarks = self.bookmarks.iter_mut().chain(
+            self.trash
+                .bookmarks
+                .iter_mut()
+                .map(|trashed| &mut trashed.bookmark),
+        );
+
+        for bookmark in bookmarks {
+            if is_id(&bookmark.id) {
+                continue;
+            }
+
+            let id = new_id();
+
+            bookmark_ids.push((bookmark.id.to_owned(), id.to_owned()));
+            bookmark.id = id;
+        }
+
+        let groups = self.groups.iter_mut().chain(
+            self.trash
+                .groups
+                .iter_mut()
+                .map(|trashed| &mut trashed.group),
+        );
+
+        for group in groups {
+            if !is_id(&group.id) {
+                let id = new_id();
+
+                group_ids.push((group.id.to_owned(), id.to_owned()));
+                group.id = id;
+            }
+
+            group.bookmarks_ids = group
+                .bookmarks_ids
+                .iter()
+                .map(|id| find_new_id(&bookmark_ids, id))
+                .collect();
+        }
+
+        for trashed in self.trash.bookmarks.iter_mut() {
+            trashed.groups_ids = trashed
+                .groups_ids
+                .iter()
+                .map(|id| find_new_id(&group_ids, id))
+                .collect();
+        }
+
+        bookmark_ids
+    }
+
+    pub fn find_bookmark(&self, query: &str) -> Option<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .find(|bookmark| bookmark.id == query)
+            .or_else(|| {
+                self.bookmarks
+                    .iter()
+                    .find(|bookmark| bookmark.name.to_lowercase() == query.to_lowercase())
+            })
+    }
+
+    pub fn find_group(&self, query: &str) -> Option<&Group> {
+        self.groups
+            .iter()
+            .find(|group| group.id == query)
+            .or_else(|| {
+                self.groups
+                    .iter()
+                    .find(|group| group.name.to_lowercase() == query.to_lowercase())
+            })
+    }
+
+    pub fn creates_cycle(&self, group_id: &str, groups_ids: &Vec<String>) -> bool {
+        let mut pending_ids = groups_ids.to_owned();
+        let mut visited_ids = Vec::<String>::new();
+
+        while let Some(id) = pending_ids.pop() {
+            if id == group_id {
+                return true;
+            }
+
+            if visited_ids.contains(&id) {
+                continue;
+            }
+
+            if let Some(group) = self.groups.iter().find(|group| group.id == id) {
+                pending_ids.extend(group.groups_ids.iter().cloned());
+            }
+
+            visited_ids.push(id);
+        }
+
+        false
+    }
+
+    pub fn trash_bookmark(&mut self, bookmark_id: &str) -> Option<Bookmark> {
+        let position = self
+            .bookmarks
+            .iter()
+            .position(|bookmark| bookmark.id == bookmark_id)?;
+
+        let bookmark = self.bookmarks.remove(position);
+        let mut groups_ids = Vec::<String>::new();
+
+        for group in self.groups.iter_mut() {
+            if group.bookmarks_ids.iter().any(|id| id == bookmark_id) {
+                group.bookmarks_ids.retain(|id| id != bookmark_id);
+                groups_ids.push(group.id.to_owned());
+            }
+        }
+
+        self.trash.bookmarks.push(TrashedBookmark {
+            bookmark: bookmark.to_owned(),
+            groups_ids,
+            deleted_at: now(),
+        });
+
+        Some(bookmark)
+    }
+
+    pub fn trash_group(&mut self, group_id: &str) -> Option<Group> {
+        let position = self.groups.iter().position(|group| group.id == group_id)?;
+        let group = self.groups.remove(position);
+
+        self.trash.groups.push(TrashedGroup {
+            group: group.to_owned(),
+            deleted_at: now(),
+        });
+
+        Some(group)
+    }
+
+    pub fn restore_bookmark(&mut self, bookmark_id: &str) -> Option<Bookmark> {
+        let position = self
+            .trash
+            .bookmarks
+            .iter()
+            .position(|trashed| trashed.bookmark.id == bookmark_id)?;
+
+        let trashed = self.trash.bookmarks.remove(position);
+
+        let groups = self.groups.iter_mut().chain(
+            self.trash
+                .groups
+                .iter_mut()
+                .map(|trashed| &mut trashed.group),
+        );
+
+        for group in groups {
+            if trashed.groups_ids.contains(&group.id)
+                && !group.bookmarks_ids.iter().any(|id| id == bookmark_id)
+            {
+                group.bookmarks_ids.push(bookmark_id.to_owned());
+            }
+        }
+
+        let bookmark = Bookmark {
+            updated_at: now(),
+            ..trashed.bookmark
         };
 
-        let json = serde_json::to_string(&db).expect("Error serializing bookmarks");
+        self.bookmarks.push(bookmark.to_owned());
+
+        Some(bookmark)
+    }
+
+    pub fn restore_group(&mut self, group_id: &str) -> Option<Group> {
+        let position = self
+            .trash
+            .groups
+            .iter()
+            .position(|trashed| trashed.group.id == group_id)?;
+
+        let trashed = self.trash.groups.remove(position);
+        let group = Group {
+            updated_at: now(),
+            ..trashed.group
+        };
+
+        self.groups.push(group.to_owned());
+
+        Some(group)
+    }
 
-        fs::write(&db_path, &json).expect("Error writing db");
+    pub fn empty_trash(&mut self, dirs: &Dirs) {
+        self.purge_trash_before(dirs, u64::MAX);
+    }
 
-        return db;
+    pub fn purge_trash(&mut self, dirs: &Dirs, days: u64) -> bool {
+        self.purge_trash_before(dirs, now().saturating_sub(days * 24 * 60 * 60))
     }
 
-    let db_json = fs::read_to_string(&db_path).expect("Error reading db");
-    let mut db = serde_json::from_str::<BookmarksDB>(&db_json).expect("Error deserializing db");
+    fn purge_trash_before(&mut self, dirs: &Dirs, cutoff: u64) -> bool {
+        let trash_size = self.trash.bookmarks.len() + self.trash.groups.len();
+
+        for trashed in &self.trash.bookmarks {
+            if trashed.deleted_at < cutoff {
+                remove_favicon(dirs, &trashed.bookmark.id);
+                remove_archive(dirs, &trashed.bookmark.id);
+
+                self.tombstones.push(Tombstone {
+                    id: trashed.bookmark.id.to_owned(),
+                    deleted_at: trashed.deleted_at,
+                });
+            }
+        }
+
+        for trashed in &self.trash.groups {
+            if trashed.deleted_at < cutoff {
+                self.tombstones.push(Tombstone {
+                    id: trashed.group.id.to_owned(),
+                    deleted_at: trashed.deleted_at,
+                });
+            }
+        }
+
+        self.trash
+            .bookmarks
+            .retain(|trashed| trashed.deleted_at >= cutoff);
 
-    db.bookmarks.sort_by_key(|bookmark| bookmark.name.clone());
-    db.groups.sort_by_key(|group| group.name.clone());
+        self.trash
+            .groups
+            .retain(|trashed| trashed.deleted_at >= cutoff);
 
-    db
+        trash_size != self.trash.bookmarks.len() + self.trash.groups.len()
+    }
 }
 
-pub fn write_db(db: &BookmarksDB) {
-    let json = serde_json::to_string(db).expect("Error serializing db");
-    fs::write(&get_db_path(), &json).expect("Error writing db");
+fn find_new_id(ids: &[(String, String)], old_id: &str) -> String {
+    ids.iter()
+        .find(|(old, _)| old == old_id)
+        .map(|(_, new)| new.to_owned())
+        .unwrap_or(old_id.to_owned())
 }
 
 impl Bookmark {
     pub fn new(name: &str, link: &str) -> Self {
-        let db = get_db();
-        let id: usize = if db.bookmarks.is_empty() {
-            0
-        } else {
-            let max = db
-                .bookmarks
-                .iter()
-                .map(|bookmark| bookmark.id)
-                .max()
-                .unwrap();
-
-            max + 1
-        };
+        let now = now();
 
         Self {
-            id,
+            id: new_id(),
             name: name.to_owned(),
             link: link.to_owned(),
+            tags: vec![],
+            folder: None,
+            private: false,
+            declared: false,
+            created_at: now,
+            updated_at: now,
+            last_opened_at: None,
+            open_count: 0,
+            archived_at: None,
+        }
+    }
+
+    pub fn mark_opened(&mut self) {
+        self.last_opened_at = Some(now());
+        self.open_count += 1;
+    }
+
+    pub fn matches_words(&self, text: &str) -> bool {
+        let searchable =
+            format!("{} {} {}", self.name, self.link, self.tags.join(" ")).to_lowercase();
+
+        text.to_lowercase()
+            .split_whitespace()
+            .all(|word| searchable.contains(word))
+    }
+
+    pub fn add_tags(&mut self, tags: &Vec<String>) {
+        for tag in tags {
+            if !self.tags.contains(tag) {
+                self.tags.push(tag.to_owned());
+            }
         }
     }
 }
 
+pub fn is_valid_link(link: &str) -> bool {
+    let url_regex = Regex::new(
+        r"https?:\/\/(www\.)?[-a-zA-Z0-9@:%._\+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b([-a-zA-Z0-9()@:%_\+.~#?&//=]*)",
+    ).unwrap();
+
+    url_regex.is_match(link)
+}
+
+pub fn parse_link(text: &str) -> Option<String> {
+    let text = text.trim();
+
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let link = match text.starts_with("http://") || text.starts_with("https://") {
+        true => text.to_owned(),
+        false => format!("https://{text}"),
+    };
+
+    is_valid_link(&link).then_some(link)
+}
+
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags = Vec::<String>::new();
+
+    for tag in text.split(',').map(|tag| tag.trim().to_lowercase()) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
 impl Group {
-    pub fn new(name: &str, bookmarks_ids: &Vec<usize>) -> Self {
-        let db = get_db();
-        let id: usize = if db.groups.is_empty() {
-            0
-        } else {
-            let max = db.groups.iter().map(|group| group.id).max().unwrap();
-
-            max + 1
-        };
+    pub fn new(name: &str, bookmarks_ids: &Vec<String>) -> Self {
+        let now = now();
 
         Self {
-            id,
+            id: new_id(),
             name: name.to_owned(),
             bookmarks_ids: bookmarks_ids.to_owned(),
+            groups_ids: vec![],
+            rule: None,
+            private: false,
+            launch: LaunchOptions::default(),
+            declared: false,
+            created_at: now,
+            updated_at: now,
+            last_opened_at: None,
+            open_count: 0,
+        }
+    }
+
+    pub fn mark_opened(&mut self) {
+        self.last_opened_at = Some(now());
+        self.open_count += 1;
+    }
+
+    pub fn get_launch_bookmarks<'a>(
+        &self,
+        bookmarks: &'a Vec<Bookmark>,
+        groups: &Vec<Group>,
+    ) -> Vec<&'a Bookmark> {
+        let mut launch_bookmarks = Vec::<&Bookmark>::new();
+
+        self.collect_bookmarks(bookmarks, groups, &mut vec![], &mut launch_bookmarks);
+
+        if let Some(max_tabs) = self.launch.max_tabs {
+            launch_bookmarks.truncate(max_tabs);
+        }
+
+        launch_bookmarks
+    }
+
+    fn collect_bookmarks<'a>(
+        &self,
+        bookmarks: &'a Vec<Bookmark>,
+        groups: &Vec<Group>,
+        visited_ids: &mut Vec<String>,
+        collected: &mut Vec<&'a Bookmark>,
+    ) {
+        // Cycles are refused when saving, but a sync can still bring one in
+        if visited_ids.contains(&self.id) {
+            return;
         }
+
+        visited_ids.push(self.id.to_owned());
+
+        let picked_bookmarks = self
+            .bookmarks_ids
+            .iter()
+            .filter_map(|id| bookmarks.iter().find(|bookmark| &bookmark.id == id));
+
+        // The rule is checked every time, so new bookmarks join the group by themselves. Private
+        // bookmarks only join private groups.
+        let rule = self.rule.as_ref().and_then(|rule| Rule::parse(rule).ok());
+
+        let matching_bookmarks = bookmarks.iter().filter(|bookmark| {
+            rule.as_ref().is_some_and(|rule| rule.matches(bookmark))
+                && (self.private || !bookmark.private)
+        });
+
+        for bookmark in picked_bookmarks.chain(matching_bookmarks) {
+            if !collected
+                .iter()
+                .any(|collected| collected.link == bookmark.link)
+            {
+                collected.push(bookmark);
+            }
+        }
+
+        for group in self
+            .groups_ids
+            .iter()
+            .filter_map(|id| groups.iter().find(|group| &group.id == id))
+        {
+            group.collect_bookmarks(bookmarks, groups, visited_ids, collected);
+        }
+    }
+}
+
+impl LaunchOptions {
+    pub fn needs_confirmation(&self, links_count: usize) -> bool {
+        self.confirm_above
+            .is_some_and(|confirm_above| links_count > confirm_above)
+    }
+}
+
+impl ReadLaterItem {
+    pub fn new(title: &str, link: &str, reading_minutes: Option<u32>) -> Self {
+        let now = now();
+
+        Self {
+            id: new_id(),
+            title: title.to_owned(),
+            link: link.to_owned(),
+            state: ReadState::Unread,
+            reading_minutes,
+            added_at: now,
+            updated_at: now,
+            read_at: None,
+        }
+    }
+
+    pub fn set_state(&mut self, state: ReadState) {
+        self.state = state;
+        self.updated_at = now();
+
+        if state == ReadState::Read {
+            self.read_at = Some(self.updated_at);
+        }
+    }
+}
+
+impl SortMode {
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "az" | "abc" => Some(SortMode::Alphabetical),
+            "new" | "newest" => Some(SortMode::Newest),
+            "recent" | "recently" => Some(SortMode::RecentlyOpened),
+            "most" | "popular" => Some(SortMode::MostOpened),
+            _ => None,
+        }
+    }
+
+    pub fn sort_bookmarks(&self, bookmarks: &mut [Bookmark]) {
+        bookmarks.sort_by_key(|bookmark| bookmark.name.to_lowercase());
+
+        match self {
+            SortMode::Alphabetical => {}
+            SortMode::Newest => bookmarks.sort_by_key(|bookmark| Reverse(bookmark.created_at)),
+            SortMode::RecentlyOpened => {
+                bookmarks.sort_by_key(|bookmark| Reverse(bookmark.last_opened_at))
+            }
+            SortMode::MostOpened => bookmarks.sort_by_key(|bookmark| Reverse(bookmark.open_count)),
+        }
+    }
+
+    pub fn sort_groups(&self, groups: &mut [Group]) {
+        groups.sort_by_key(|group| group.name.to_lowercase());
+
+        match self {
+            SortMode::Alphabetical => {}
+            SortMode::Newest => groups.sort_by_key(|group| Reverse(group.created_at)),
+            SortMode::RecentlyOpened => groups.sort_by_key(|group| Reverse(group.last_opened_at)),
+            SortMode::MostOpened => groups.sort_by_key(|group| Reverse(group.open_count)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_id, Bookmark, BookmarksDB, Group, DB_VERSION};
+
+    fn old_db() -> BookmarksDB {
+        serde_json::from_str(
+            r#"{
+                "bookmarks": [
+                    { "id": 1, "name": "Rust", "link": "https://rust-lang.org" },
+                    { "id": 2, "name": "Docs", "link": "https://docs.rs" }
+                ],
+                "groups": [
+                    { "id": 1, "name": "Work", "bookmarks_ids": [1, 2, 7] }
+                ],
+                "trash": {
+                    "bookmarks": [
+                        {
+                            "bookmark": { "id": 3, "name": "Crates", "link": "https://crates.io" },
+                            "groups_ids": [1, 9],
+                            "deleted_at": 10
+                        }
+                    ],
+                    "groups": []
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn gives_old_numeric_ids_a_uuid() {
+        let mut db = old_db();
+        let bookmark_ids = db.migrate().unwrap();
+
+        assert_eq!(db.version, DB_VERSION);
+        assert_eq!(bookmark_ids.len(), 3);
+        assert!(db.bookmarks.iter().all(|bookmark| is_id(&bookmark.id)));
+        assert!(is_id(&db.groups[0].id));
+        assert!(is_id(&db.trash.bookmarks[0].bookmark.id));
+        assert_eq!(
+            bookmark_ids[0],
+            ("1".to_owned(), db.bookmarks[0].id.to_owned())
+        );
+    }
+
+    #[test]
+    fn remaps_the_group_references() {
+        let mut db = old_db();
+        db.migrate();
+
+        let group = &db.groups[0];
+
+        assert_eq!(group.bookmarks_ids[0], db.bookmarks[0].id);
+        assert_eq!(group.bookmarks_ids[1], db.bookmarks[1].id);
+        assert_eq!(db.trash.bookmarks[0].groups_ids[0], group.id);
+    }
+
+    #[test]
+    fn keeps_the_references_it_has_no_new_id_for() {
+        let mut db = old_db();
+        db.migrate();
+
+        assert_eq!(db.groups[0].bookmarks_ids[2], "7");
+        assert_eq!(db.trash.bookmarks[0].groups_ids[1], "9");
+    }
+
+    #[test]
+    fn migrates_only_once() {
+        let mut db = old_db();
+        db.migrate();
+
+        let migrated = db.to_owned();
+
+        assert_eq!(db.migrate(), None);
+        assert_eq!(db, migrated);
+    }
+
+    fn nested_group(name: &str, bookmarks: &[&Bookmark], groups: &[&Group]) -> Group {
+        let bookmarks_ids = bookmarks
+            .iter()
+            .map(|bookmark| bookmark.id.to_owned())
+            .collect();
+
+        Group {
+            groups_ids: groups.iter().map(|group| group.id.to_owned()).collect(),
+            ..Group::new(name, &bookmarks_ids)
+        }
+    }
+
+    #[test]
+    fn finds_a_direct_cycle() {
+        let inner = nested_group("Inner", &[], &[]);
+        let outer = nested_group("Outer", &[], &[&inner]);
+        let db = BookmarksDB {
+            groups: vec![outer.to_owned(), inner.to_owned()],
+            ..Default::default()
+        };
+
+        assert!(db.creates_cycle(&inner.id, &vec![inner.id.to_owned()]));
+        assert!(db.creates_cycle(&inner.id, &vec![outer.id.to_owned()]));
+    }
+
+    #[test]
+    fn finds_an_indirect_cycle() {
+        let bottom = nested_group("Bottom", &[], &[]);
+        let middle = nested_group("Middle", &[], &[&bottom]);
+        let top = nested_group("Top", &[], &[&middle]);
+        let db = BookmarksDB {
+            groups: vec![top.to_owned(), middle, bottom.to_owned()],
+            ..Default::default()
+        };
+
+        assert!(db.creates_cycle(&bottom.id, &vec![top.id.to_owned()]));
+    }
+
+    #[test]
+    fn launches_a_diamond_once() {
+        let rust = Bookmark::new("Rust", "https://rust-lang.org");
+        let docs = Bookmark::new("Docs", "https://docs.rs");
+        let crates = Bookmark::new("Crates", "https://crates.io");
+
+        let bottom = nested_group("Bottom", &[&rust, &docs], &[]);
+        let left = nested_group("Left", &[&rust], &[&bottom]);
+        let right = nested_group("Right", &[&crates], &[&bottom]);
+        let top = nested_group("Top", &[], &[&left, &right]);
+        let db = BookmarksDB {
+            bookmarks: vec![rust, docs, crates],
+            groups: vec![top.to_owned(), left, right, bottom],
+            ..Default::default()
+        };
+
+        assert!(!db.creates_cycle(&top.id, &top.groups_ids));
+
+        let links = top
+            .get_launch_bookmarks(&db.bookmarks, &db.groups)
+            .iter()
+            .map(|bookmark| bookmark.link.as_str())
+            .collect::<Vec<&str>>();
+
+        assert_eq!(
+            links,
+            vec![
+                "https://rust-lang.org",
+                "https://docs.rs",
+                "https://crates.io"
+            ]
+        );
+    }
+
+    #[test]
+    fn launches_a_synced_cycle_without_looping() {
+        let rust = Bookmark::new("Rust", "https://rust-lang.org");
+        let mut first = nested_group("First", &[&rust], &[]);
+        let second = nested_group("Second", &[&rust], &[&first]);
+        first.groups_ids = vec![second.id.to_owned()];
+
+        let db = BookmarksDB {
+            bookmarks: vec![rust],
+            groups: vec![first.to_owned(), second],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            first.get_launch_bookmarks(&db.bookmarks, &db.groups).len(),
+            1
+        );
     }
 }
@@ -0,0 +1,858 @@
+use std::{fs, io::Read, path::PathBuf, process::exit};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use sniffer_rs::sniffer::Sniffer;
+
+use crate::{
+    archives::archive_page,
+    bookmarks::{is_valid_link, now, parse_tags, Bookmark, BookmarksDB, Group, SortMode},
+    clipboard::{copy_text, hold_text, LinkFormat},
+    collections::{refresh_collections, refresh_locked_collections, remove_collection_cache},
+    encryption::KeySource,
+    export::{to_bookmarks_html, to_json},
+    favicons::{write_favicon, write_favicons},
+    http_sync::set_http_password,
+    import::{import_bookmarks, import_db, parse_bookmarks_html},
+    launch::open_group,
+    paths::Dirs,
+    profiles::{create_profile, get_profiles, is_profile, switch_profile},
+    rules::Rule,
+    settings::{get_settings, write_settings, CollectionSource},
+    storage::{open_store, EncryptedStore, StorageBackend},
+    store::{sync_db, sync_pending},
+};
+
+/// Manage the tigris bookmarks from scripts and dotfiles
+#[derive(Parser)]
+#[command(name = "bookmarks")]
+struct Cli {
+    /// Print JSON instead of a table
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Use this profile instead of the active one
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add a bookmark
+    Add {
+        name: String,
+        link: String,
+        /// Tags separated by commas
+        #[arg(long)]
+        tags: Option<String>,
+        #[arg(long)]
+        folder: Option<String>,
+        /// Only show the bookmark under the private keyword
+        #[arg(long)]
+        private: bool,
+    },
+    /// List the bookmarks, optionally only the ones matching the search text
+    List {
+        search: Option<String>,
+        /// One of az, newest, recent or most
+        #[arg(long, default_value = "az")]
+        sort: String,
+    },
+    /// Move a bookmark to the trash
+    Rm {
+        /// The bookmark id or name
+        bookmark: String,
+    },
+    /// Edit a bookmark
+    Edit {
+        /// The bookmark id or name
+        bookmark: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        link: Option<String>,
+        /// Tags separated by commas. Replaces the current tags.
+        #[arg(long)]
+        tags: Option<String>,
+        /// The folder name. An empty name removes the bookmark from its folder.
+        #[arg(long)]
+        folder: Option<String>,
+        /// true to only show the bookmark under the private keyword
+        #[arg(long)]
+        private: Option<bool>,
+    },
+    /// Import a browser html export or a json export of this extension
+    Import { file: PathBuf },
+    /// Export the bookmarks and groups
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Write to a file instead of the standard output
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Open a bookmark in the browser
+    Open {
+        /// The bookmark id or name
+        bookmark: String,
+    },
+    /// Save an offline copy of a bookmark's page
+    Archive {
+        /// The bookmark id or name
+        bookmark: String,
+    },
+    /// Copy a bookmark's link to the clipboard
+    Copy {
+        /// The bookmark id or name
+        bookmark: String,
+        /// One of link, markdown or html
+        #[arg(long, default_value = "link")]
+        format: String,
+    },
+    /// Keeps the text read from stdin in the clipboard on Linux for a while
+    #[command(hide = true)]
+    HoldClipboard,
+    /// Manage the groups
+    #[command(subcommand)]
+    Group(GroupCommand),
+    /// Manage the shared collections
+    #[command(subcommand)]
+    Collection(CollectionCommand),
+    /// Pull and push the bookmarks with the configured git remote or http url
+    Sync {
+        /// Sync the writes made since the last sync, run after every write
+        #[arg(long, hide = true)]
+        pending: bool,
+    },
+    /// Keep the http sync password in the system keyring. It's read from stdin.
+    SyncPassword,
+    /// Manage the profiles
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Encrypt the db again with a new key. With a passphrase, the new one is read from stdin.
+    RotateKey,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List the profiles
+    List,
+    /// Add a profile
+    Add { name: String },
+    /// Use a profile from now on
+    Switch { name: String },
+}
+
+#[derive(Subcommand)]
+enum GroupCommand {
+    /// List the groups
+    List,
+    /// Add a group with the given bookmarks
+    Add {
+        name: String,
+        /// The bookmarks ids or names
+        bookmarks: Vec<String>,
+        /// A group to put inside this one. Can be repeated.
+        #[arg(long = "group")]
+        groups: Vec<String>,
+        /// Also add every bookmark matching a rule, like "tag:infra opened:7d"
+        #[arg(long)]
+        rule: Option<String>,
+    },
+    /// Move a group to the trash
+    Rm {
+        /// The group id or name
+        group: String,
+    },
+    /// Open every bookmark in a group
+    Open {
+        /// The group id or name
+        group: String,
+        /// Only print the links that would open
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CollectionCommand {
+    /// List the collections
+    List,
+    /// Subscribe to a collection
+    Add {
+        name: String,
+        /// The path or url of a json or html bookmarks file
+        source: String,
+        /// How often the collection is downloaded again
+        #[arg(long, default_value_t = 60)]
+        refresh_minutes: u64,
+    },
+    /// Unsubscribe from a collection
+    Rm { name: String },
+    /// Download the stale collections again
+    Refresh {
+        /// Also download the ones that aren't stale
+        #[arg(long)]
+        force: bool,
+        /// Run by the background refresh, which already holds the lock
+        #[arg(long, hide = true)]
+        locked: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Html,
+}
+
+/// Row printed by `group list`
+#[derive(Serialize)]
+struct GroupRow {
+    id: String,
+    name: String,
+    bookmarks: Vec<String>,
+    groups: Vec<String>,
+    rule: Option<String>,
+}
+
+pub fn is_cli_invocation() -> bool {
+    std::env::args().len() > 1
+}
+
+pub async fn run_cli() {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    let mut dirs = Dirs::active();
+
+    if let Some(profile) = &cli.profile {
+        if !is_profile(&dirs.data_dir, profile) {
+            fail(&format!("No profile called {profile}"));
+        }
+
+        dirs = dirs.with_profile(profile);
+    }
+
+    let store = match open_store(&dirs) {
+        Ok(store) => store,
+        Err(error) => fail(&error),
+    };
+
+    // An encrypted db without its key is an error rather than a panic in the first command that reads it
+    if !matches!(cli.command, Command::HoldClipboard) {
+        if let Err(error) = store.read() {
+            fail(&format!("The bookmarks couldn't be read: {error}"));
+        }
+    }
+
+    match cli.command {
+        Command::Add {
+            name,
+            link,
+            tags,
+            folder,
+            private,
+        } => {
+            if !is_valid_link(&link) {
+                fail("The provided link isn't a valid link");
+            }
+
+            let mut db = store.get_db();
+            let bookmark = Bookmark {
+                tags: parse_tags(&tags.unwrap_or_default()),
+                folder: folder.filter(|folder| !folder.trim().is_empty()),
+                private,
+                ..Bookmark::new(&name, &link)
+            };
+
+            write_favicon(&dirs, &bookmark).await;
+
+            db.bookmarks.push(bookmark.to_owned());
+            store.write_db(&db);
+
+            print_bookmarks(&vec![bookmark], json);
+        }
+        Command::List { search, sort } => {
+            let mut db = store.get_db();
+            let sniffer = Sniffer::new();
+            let sort_mode = match SortMode::from_keyword(&sort) {
+                Some(sort_mode) => sort_mode,
+                None => fail("The sort must be one of az, newest, recent or most"),
+            };
+
+            sort_mode.sort_bookmarks(&mut db.bookmarks);
+
+            let bookmarks = db
+                .bookmarks
+                .into_iter()
+                .filter(|bookmark| match &search {
+                    Some(search) => sniffer.matches(&bookmark.name, search),
+                    None => true,
+                })
+                .collect::<Vec<Bookmark>>();
+
+            print_bookmarks(&bookmarks, json);
+        }
+        Command::Rm { bookmark } => {
+            let mut db = store.get_db();
+            let bookmark_id = find_bookmark_id(&db, &bookmark);
+
+            if db
+                .find_bookmark(&bookmark_id)
+                .is_some_and(|bookmark| bookmark.declared)
+            {
+                fail(&format!(
+                    "{bookmark} is declared in bookmarks.toml, remove it from the file instead"
+                ));
+            }
+
+            let bookmark = match db.trash_bookmark(&bookmark_id) {
+                Some(bookmark) => bookmark,
+                None => fail(&format!("No bookmark called {bookmark}")),
+            };
+
+            store.write_db(&db);
+
+            print_bookmarks(&vec![bookmark], json);
+        }
+        Command::Edit {
+            bookmark,
+            name,
+            link,
+            tags,
+            folder,
+            private,
+        } => {
+            let mut db = store.get_db();
+            let bookmark_id = find_bookmark_id(&db, &bookmark);
+
+            if let Some(link) = &link {
+                if !is_valid_link(link) {
+                    fail("The provided link isn't a valid link");
+                }
+            }
+
+            let bookmark = db
+                .bookmarks
+                .iter_mut()
+                .find(|bookmark| bookmark.id == bookmark_id)
+                .unwrap();
+
+            if let Some(name) = name {
+                bookmark.name = name;
+            }
+
+            if let Some(link) = &link {
+                bookmark.link = link.to_owned();
+            }
+
+            if let Some(tags) = tags {
+                bookmark.tags = parse_tags(&tags);
+            }
+
+            if let Some(folder) = folder {
+                bookmark.folder = Some(folder).filter(|folder| !folder.trim().is_empty());
+            }
+
+            if let Some(private) = private {
+                bookmark.private = private;
+            }
+
+            bookmark.updated_at = now();
+
+            let bookmark = bookmark.to_owned();
+
+            if link.is_some() || private.is_some() {
+                write_favicon(&dirs, &bookmark).await;
+            }
+
+            store.write_db(&db);
+
+            print_bookmarks(&vec![bookmark], json);
+        }
+        Command::Import { file } => {
+            let content = match fs::read_to_string(&file) {
+                Ok(content) => content,
+                Err(_) => fail("The file couldn't be read"),
+            };
+
+            let mut db = store.get_db();
+
+            let imported = match serde_json::from_str(&content) {
+                Ok(other) => import_db(&mut db, other),
+                Err(_) => import_bookmarks(&mut db, parse_bookmarks_html(&content)),
+            };
+
+            write_favicons(&dirs, &imported).await;
+            store.write_db(&db);
+
+            print_bookmarks(&imported, json);
+        }
+        Command::Export { format, output } => {
+            let db = store.get_db();
+
+            let export = match format {
+                ExportFormat::Json => to_json(&db),
+                ExportFormat::Html => to_bookmarks_html(&db),
+            };
+
+            match output {
+                Some(output) => {
+                    if let Err(error) = fs::write(&output, &export) {
+                        fail(&format!("Error writing {}: {error}", output.display()));
+                    }
+                }
+                None => println!("{export}"),
+            }
+        }
+        Command::Open { bookmark } => {
+            let mut db = store.get_db();
+            let bookmark_id = find_bookmark_id(&db, &bookmark);
+
+            let bookmark = match db
+                .bookmarks
+                .iter_mut()
+                .find(|bookmark| bookmark.id == bookmark_id)
+            {
+                Some(bookmark) => bookmark,
+                None => fail(&format!("No bookmark called {bookmark}")),
+            };
+
+            if let Err(error) = open::that(&bookmark.link) {
+                fail(&format!("Error opening {}: {error}", bookmark.name));
+            }
+
+            bookmark.mark_opened();
+
+            store.write_db(&db);
+        }
+        Command::Archive { bookmark } => {
+            let db = store.get_db();
+            let bookmark_id = find_bookmark_id(&db, &bookmark);
+            let mut bookmark = store.get(&bookmark_id).unwrap();
+
+            match archive_page(&dirs, &bookmark).await {
+                Ok(archive_path) => {
+                    bookmark.archived_at = Some(now());
+                    store.update(&bookmark);
+
+                    println!("{}", archive_path.display());
+                }
+                Err(error) => fail(&format!("Error archiving {}: {error}", bookmark.name)),
+            }
+        }
+        Command::Copy { bookmark, format } => {
+            let db = store.get_db();
+            let bookmark_id = find_bookmark_id(&db, &bookmark);
+            let bookmark = match store.get(&bookmark_id) {
+                Some(bookmark) => bookmark,
+                None => fail(&format!("No bookmark called {bookmark}")),
+            };
+
+            let format = match LinkFormat::from_name(&format) {
+                Some(format) => format,
+                None => fail(&format!("Unknown format {format}")),
+            };
+
+            if bookmark.private {
+                fail("Private bookmarks aren't copied");
+            }
+
+            if let Err(error) = copy_text(&format.format(&bookmark)) {
+                fail(&format!("Error copying {}: {error}", bookmark.name));
+            }
+        }
+        Command::HoldClipboard => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .expect("Error reading the copied text");
+
+            if let Err(error) = hold_text(&text) {
+                fail(&format!("Error copying: {error}"));
+            }
+        }
+        Command::Group(GroupCommand::List) => {
+            let db = store.get_db();
+
+            print_groups(&db.groups, &db, json);
+        }
+        Command::Group(GroupCommand::Add {
+            name,
+            bookmarks,
+            groups,
+            rule,
+        }) => {
+            let mut db = store.get_db();
+
+            if let Some(Err(error)) = rule.as_ref().map(|rule| Rule::parse(rule)) {
+                fail(&format!("Invalid rule: {error}"));
+            }
+
+            let bookmarks_ids = bookmarks
+                .iter()
+                .map(|bookmark| find_bookmark_id(&db, bookmark))
+                .collect::<Vec<String>>();
+
+            let group = Group {
+                groups_ids: groups
+                    .iter()
+                    .map(|group| find_group_id(&db, group))
+                    .collect(),
+                rule,
+                ..Group::new(&name, &bookmarks_ids)
+            };
+
+            db.groups.push(group.to_owned());
+            store.write_db(&db);
+
+            print_groups(&[group], &db, json);
+        }
+        Command::Group(GroupCommand::Rm { group }) => {
+            let mut db = store.get_db();
+            let group_id = find_group_id(&db, &group);
+
+            if db.find_group(&group_id).is_some_and(|group| group.declared) {
+                fail(&format!(
+                    "{group} is declared in bookmarks.toml, remove it from the file instead"
+                ));
+            }
+
+            let group = match db.trash_group(&group_id) {
+                Some(group) => group,
+                None => fail(&format!("No group called {group}")),
+            };
+
+            store.write_db(&db);
+
+            print_groups(&[group], &db, json);
+        }
+        Command::Group(GroupCommand::Open { group, dry_run }) => {
+            let mut db = store.get_db();
+            let group_id = find_group_id(&db, &group);
+
+            if dry_run {
+                let group = db.groups.iter().find(|group| group.id == group_id).unwrap();
+                let bookmarks = group
+                    .get_launch_bookmarks(&db.bookmarks, &db.groups)
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<Bookmark>>();
+
+                print_bookmarks(&bookmarks, json);
+                return;
+            }
+
+            if let Err(error) = open_group(&mut db, &group_id, None) {
+                fail(&error);
+            }
+
+            store.write_db(&db);
+        }
+        Command::Collection(CollectionCommand::List) => {
+            print_collections(&get_settings(&dirs).collections, json);
+        }
+        Command::Collection(CollectionCommand::Add {
+            name,
+            source,
+            refresh_minutes,
+        }) => {
+            let mut settings = get_settings(&dirs);
+
+            if settings
+                .collections
+                .iter()
+                .any(|collection| collection.name == name)
+            {
+                fail(&format!("There's already a collection called {name}"));
+            }
+
+            let collection = CollectionSource {
+                name,
+                source,
+                refresh_minutes,
+            };
+
+            settings.collections.push(collection.to_owned());
+            write_settings(&dirs, &settings);
+
+            for (name, result) in refresh_collections(&dirs, false).await {
+                match result {
+                    Ok(count) => eprintln!("{name}: {count} bookmarks"),
+                    Err(error) => eprintln!("{name}: {error}"),
+                }
+            }
+
+            print_collections(&vec![collection], json);
+        }
+        Command::Collection(CollectionCommand::Rm { name }) => {
+            let mut settings = get_settings(&dirs);
+
+            let collection = match settings
+                .collections
+                .iter()
+                .find(|collection| collection.name == name)
+            {
+                Some(collection) => collection.to_owned(),
+                None => fail(&format!("No collection called {name}")),
+            };
+
+            settings
+                .collections
+                .retain(|collection| collection.name != name);
+
+            write_settings(&dirs, &settings);
+            remove_collection_cache(&dirs, &name);
+
+            print_collections(&vec![collection], json);
+        }
+        Command::Collection(CollectionCommand::Refresh { locked: true, .. }) => {
+            refresh_locked_collections(&dirs).await;
+        }
+        Command::Collection(CollectionCommand::Refresh { force, .. }) => {
+            for (name, result) in refresh_collections(&dirs, force).await {
+                match result {
+                    Ok(count) => println!("{name}: {count} bookmarks"),
+                    Err(error) => eprintln!("{name}: {error}"),
+                }
+            }
+        }
+        Command::Sync { pending: true } => sync_pending(store.as_ref()).await,
+        Command::Sync { pending: false } => match sync_db(store.as_ref()).await {
+            Ok(true) => println!("Synced the remote changes"),
+            Ok(false) => println!("Up to date"),
+            Err(error) => fail(&error),
+        },
+        Command::SyncPassword => {
+            let settings = get_settings(&dirs);
+
+            if settings.http_sync_username.is_empty() {
+                fail("Set the http sync username in the settings first");
+            }
+
+            eprintln!("Password:");
+
+            let mut password = String::new();
+            std::io::stdin()
+                .read_line(&mut password)
+                .expect("Error reading password");
+
+            match set_http_password(&settings, password.trim_end_matches(['\r', '\n'])) {
+                Ok(()) => println!("Saved the password in the keyring"),
+                Err(error) => fail(&error),
+            }
+        }
+        Command::RotateKey => {
+            let settings = get_settings(&dirs);
+
+            if settings.storage != StorageBackend::Encrypted {
+                fail("The db isn't encrypted, pick the encrypted storage in the settings first");
+            }
+
+            let new_passphrase = match settings.encryption_key {
+                KeySource::Keyring => None,
+                KeySource::Passphrase => {
+                    eprintln!("New passphrase:");
+
+                    let mut passphrase = String::new();
+                    std::io::stdin()
+                        .read_line(&mut passphrase)
+                        .expect("Error reading passphrase");
+
+                    Some(passphrase.trim_end_matches(['\r', '\n']).to_owned())
+                }
+            };
+
+            if new_passphrase
+                .as_ref()
+                .is_some_and(|passphrase| passphrase.is_empty())
+            {
+                fail("The passphrase can't be empty");
+            }
+
+            match EncryptedStore::new(&dirs, settings.encryption_key)
+                .rotate_key(new_passphrase.as_deref())
+            {
+                Ok(()) => println!("Encrypted the db with a new key"),
+                Err(error) => fail(&error),
+            }
+        }
+        Command::Profile(ProfileCommand::List) => {
+            for profile in get_profiles(&dirs.data_dir) {
+                if profile == dirs.profile {
+                    println!("* {profile}");
+                } else {
+                    println!("  {profile}");
+                }
+            }
+        }
+        Command::Profile(ProfileCommand::Add { name }) => {
+            if let Err(error) = create_profile(&dirs.data_dir, &name) {
+                fail(&error);
+            }
+
+            println!("Added the {name} profile");
+        }
+        Command::Profile(ProfileCommand::Switch { name }) => {
+            if let Err(error) = switch_profile(&dirs.data_dir, &name) {
+                fail(&error);
+            }
+
+            println!("Switched to the {name} profile");
+        }
+    }
+}
+
+fn print_collections(collections: &Vec<CollectionSource>, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(collections).expect("Error serializing collections")
+        );
+
+        return;
+    }
+
+    let rows = collections
+        .iter()
+        .map(|collection| {
+            vec![
+                collection.name.to_owned(),
+                collection.source.to_owned(),
+                collection.refresh_minutes.to_string(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    print_table(&["NAME", "SOURCE", "REFRESH MINUTES"], &rows);
+}
+
+fn find_bookmark_id(db: &BookmarksDB, query: &str) -> String {
+    match db.find_bookmark(query) {
+        Some(bookmark) => bookmark.id.to_owned(),
+        None => fail(&format!("No bookmark called {query}")),
+    }
+}
+
+fn find_group_id(db: &BookmarksDB, query: &str) -> String {
+    match db.find_group(query) {
+        Some(group) => group.id.to_owned(),
+        None => fail(&format!("No group called {query}")),
+    }
+}
+
+fn print_bookmarks(bookmarks: &Vec<Bookmark>, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(bookmarks).expect("Error serializing bookmarks")
+        );
+
+        return;
+    }
+
+    let rows = bookmarks
+        .iter()
+        .map(|bookmark| {
+            vec![
+                bookmark.id.to_owned(),
+                bookmark.name.to_owned(),
+                bookmark.link.to_owned(),
+                bookmark.tags.join(", "),
+                bookmark.folder.to_owned().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    print_table(&["ID", "NAME", "LINK", "TAGS", "FOLDER"], &rows);
+}
+
+fn print_groups(groups: &[Group], db: &BookmarksDB, json: bool) {
+    let group_rows = groups
+        .iter()
+        .map(|group| GroupRow {
+            id: group.id.to_owned(),
+            name: group.name.to_owned(),
+            bookmarks: db
+                .bookmarks
+                .iter()
+                .filter(|bookmark| group.bookmarks_ids.contains(&bookmark.id))
+                .map(|bookmark| bookmark.name.to_owned())
+                .collect(),
+            groups: db
+                .groups
+                .iter()
+                .filter(|other| group.groups_ids.contains(&other.id))
+                .map(|other| other.name.to_owned())
+                .collect(),
+            rule: group.rule.to_owned(),
+        })
+        .collect::<Vec<GroupRow>>();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&group_rows).expect("Error serializing groups")
+        );
+
+        return;
+    }
+
+    let rows = group_rows
+        .into_iter()
+        .map(|group| {
+            vec![
+                group.id,
+                group.name,
+                group.bookmarks.join(", "),
+                group.groups.join(", "),
+                group.rule.unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    print_table(&["ID", "NAME", "BOOKMARKS", "GROUPS", "RULE"], &rows);
+}
+
+fn print_table(headers: &[&str], rows: &Vec<Vec<String>>) {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<usize>>();
+
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+
+    println!(
+        "{}",
+        format_row(headers.iter().map(|header| header.to_string()).collect())
+    );
+
+    for row in rows {
+        println!("{}", format_row(row.to_owned()));
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    exit(1);
+}
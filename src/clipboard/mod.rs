@@ -0,0 +1,125 @@
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use arboard::Clipboard;
+
+use crate::{
+    bookmarks::{is_valid_link, Bookmark},
+    export::encode_entities,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkFormat {
+    Link,
+    Markdown,
+    Html,
+}
+
+impl LinkFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "link" => Some(Self::Link),
+            "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Link => "link",
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+        }
+    }
+
+    pub fn format(&self, bookmark: &Bookmark) -> String {
+        match self {
+            Self::Link => bookmark.link.to_owned(),
+            Self::Markdown => {
+                let name = bookmark.name.replace('[', "\\[").replace(']', "\\]");
+
+                let link = bookmark
+                    .link
+                    .replace('(', "%28")
+                    .replace(')', "%29")
+                    .replace(' ', "%20");
+
+                format!("[{name}]({link})")
+            }
+            Self::Html => format!(
+                "<a href=\"{}\">{}</a>",
+                encode_entities(&bookmark.link),
+                encode_entities(&bookmark.name)
+            ),
+        }
+    }
+}
+
+pub fn get_clipboard_link() -> Option<String> {
+    let text = Clipboard::new().ok()?.get_text().ok()?;
+    let text = text.trim();
+
+    let is_link = (text.starts_with("http://") || text.starts_with("https://"))
+        && !text.contains(char::is_whitespace)
+        && is_valid_link(text);
+
+    is_link.then(|| text.to_owned())
+}
+
+pub fn copy_text(text: &str) -> Result<(), String> {
+    if cfg!(target_os = "linux") {
+        let executable = env::current_exe().map_err(|error| error.to_string())?;
+
+        let mut holder = Command::new(executable)
+            .arg("hold-clipboard")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| error.to_string())?;
+
+        // Dropping stdin closes it, which is how the holder knows the text is complete
+        let mut stdin = holder
+            .stdin
+            .take()
+            .expect("Error opening clipboard holder stdin");
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|error| error.to_string())?;
+
+        return Ok(());
+    }
+
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(target_os = "linux")]
+pub fn hold_text(text: &str) -> Result<(), String> {
+    use arboard::SetExtLinux;
+    use std::time::{Duration, Instant};
+
+    // Long enough to paste it, short enough to not leave the process around
+    const HOLD_DURATION: Duration = Duration::from_secs(10 * 60);
+
+    Clipboard::new()
+        .and_then(|mut clipboard| {
+            clipboard
+                .set()
+                .wait_until(Instant::now() + HOLD_DURATION)
+                .text(text.to_owned())
+        })
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn hold_text(text: &str) -> Result<(), String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|error| error.to_string())
+}
@@ -0,0 +1,261 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::{is_valid_link, now, BookmarksDB},
+    import::parse_bookmarks_html,
+    paths::{
+        get_collection_cache_path, get_collection_lock_path, get_collections_dir, Dirs,
+        DATA_DIR_VAR,
+    },
+    profiles::PROFILE_VAR,
+    settings::{get_settings, CollectionSource},
+};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+const LOCK_SECONDS: u64 = 5 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CollectionCache {
+    pub fetched_at: u64,
+    pub bookmarks: Vec<CollectionBookmark>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CollectionBookmark {
+    pub name: String,
+    pub link: String,
+}
+
+pub fn get_collection_cache(dirs: &Dirs, collection: &CollectionSource) -> Option<CollectionCache> {
+    let cache_json = fs::read_to_string(get_collection_cache_path(dirs, &collection.name)).ok()?;
+    serde_json::from_str::<CollectionCache>(&cache_json).ok()
+}
+
+fn write_collection_cache(dirs: &Dirs, collection: &CollectionSource, cache: &CollectionCache) {
+    let collections_dir = get_collections_dir(dirs);
+
+    if !collections_dir.exists() {
+        fs::create_dir_all(&collections_dir).expect("Error creating collections directory");
+    }
+
+    let json = serde_json::to_string(cache).expect("Error serializing collection");
+    fs::write(get_collection_cache_path(dirs, &collection.name), &json)
+        .expect("Error writing collection");
+}
+
+pub fn remove_collection_cache(dirs: &Dirs, name: &str) {
+    let cache_path = get_collection_cache_path(dirs, name);
+
+    if cache_path.exists() {
+        fs::remove_file(cache_path).expect("Error deleting collection");
+    }
+}
+
+pub fn is_stale(dirs: &Dirs, collection: &CollectionSource) -> bool {
+    match get_collection_cache(dirs, collection) {
+        Some(cache) => now() >= cache.fetched_at + collection.refresh_minutes * 60,
+        None => true,
+    }
+}
+
+pub fn spawn_refresh(dirs: &Dirs) {
+    if !lock_refresh(dirs) {
+        return;
+    }
+
+    let spawned = env::current_exe().and_then(|executable| {
+        Command::new(executable)
+            .args(["collection", "refresh", "--locked"])
+            .env(DATA_DIR_VAR, &dirs.data_dir)
+            .env(PROFILE_VAR, &dirs.profile)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+
+    if spawned.is_err() {
+        unlock_refresh(dirs);
+    }
+}
+
+fn lock_refresh(dirs: &Dirs) -> bool {
+    let collections_dir = get_collections_dir(dirs);
+
+    if !collections_dir.exists() {
+        fs::create_dir_all(&collections_dir).expect("Error creating collections directory");
+    }
+
+    let lock_path = get_collection_lock_path(dirs);
+
+    let is_stale_lock = fs::read_to_string(&lock_path)
+        .ok()
+        .and_then(|locked_at| locked_at.trim().parse::<u64>().ok())
+        .is_some_and(|locked_at| now() >= locked_at + LOCK_SECONDS);
+
+    if is_stale_lock {
+        unlock_refresh(dirs);
+    }
+
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_path)
+    {
+        Ok(_) => {
+            fs::write(&lock_path, now().to_string()).expect("Error writing refresh lock");
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn unlock_refresh(dirs: &Dirs) {
+    let _ = fs::remove_file(get_collection_lock_path(dirs));
+}
+
+pub async fn refresh_collections(dirs: &Dirs, force: bool) -> Vec<(String, Result<usize, String>)> {
+    let locked = lock_refresh(dirs);
+    let refreshed = fetch_collections(dirs, force).await;
+
+    if locked {
+        unlock_refresh(dirs);
+    }
+
+    refreshed
+}
+
+pub async fn refresh_locked_collections(dirs: &Dirs) -> Vec<(String, Result<usize, String>)> {
+    let refreshed = fetch_collections(dirs, false).await;
+
+    unlock_refresh(dirs);
+
+    refreshed
+}
+
+async fn fetch_collections(dirs: &Dirs, force: bool) -> Vec<(String, Result<usize, String>)> {
+    let mut refreshed = Vec::<(String, Result<usize, String>)>::new();
+
+    for collection in get_settings(dirs).collections {
+        if !force && !is_stale(dirs, &collection) {
+            continue;
+        }
+
+        let mut cache = get_collection_cache(dirs, &collection).unwrap_or_default();
+        cache.fetched_at = now();
+
+        match fetch_collection(&collection).await {
+            Ok(bookmarks) => {
+                refreshed.push((collection.name.to_owned(), Ok(bookmarks.len())));
+                cache.bookmarks = bookmarks;
+                cache.error = None;
+            }
+            Err(error) => {
+                refreshed.push((collection.name.to_owned(), Err(error.to_owned())));
+                cache.error = Some(error);
+            }
+        }
+
+        write_collection_cache(dirs, &collection, &cache);
+    }
+
+    refreshed
+}
+
+async fn fetch_collection(
+    collection: &CollectionSource,
+) -> Result<Vec<CollectionBookmark>, String> {
+    let source = &collection.source;
+
+    let content = if source.starts_with("http://") || source.starts_with("https://") {
+        let client = Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|error| error.to_string())?;
+
+        let response = client
+            .get(source)
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("The server answered {}", response.status()));
+        }
+
+        response.text().await.map_err(|error| error.to_string())?
+    } else {
+        fs::read_to_string(source).map_err(|error| error.to_string())?
+    };
+
+    parse_collection(&content)
+}
+
+fn parse_collection(content: &str) -> Result<Vec<CollectionBookmark>, String> {
+    let trimmed = content.trim_start();
+
+    let bookmarks = if trimmed.starts_with('{') {
+        let db = serde_json::from_str::<BookmarksDB>(content).map_err(|error| error.to_string())?;
+
+        db.bookmarks
+            .into_iter()
+            .map(|bookmark| CollectionBookmark {
+                name: bookmark.name,
+                link: bookmark.link,
+            })
+            .collect()
+    } else if trimmed.starts_with('[') {
+        serde_json::from_str::<Vec<CollectionBookmark>>(content)
+            .map_err(|error| error.to_string())?
+    } else {
+        parse_bookmarks_html(content)
+            .into_iter()
+            .map(|bookmark| CollectionBookmark {
+                name: bookmark.name,
+                link: bookmark.link,
+            })
+            .collect::<Vec<CollectionBookmark>>()
+    };
+
+    // Collections come from third parties, so links like file: or javascript: are left out
+    Ok(bookmarks
+        .into_iter()
+        .filter(|bookmark| is_web_link(&bookmark.link))
+        .collect())
+}
+
+fn is_web_link(link: &str) -> bool {
+    (link.starts_with("http://") || link.starts_with("https://")) && is_valid_link(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_collection;
+
+    #[test]
+    fn leaves_out_links_that_arent_web_pages() {
+        let bookmarks = parse_collection(
+            r#"[
+                { "name": "Rust", "link": "https://rust-lang.org" },
+                { "name": "Passwords", "link": "file:///etc/passwd" },
+                { "name": "Script", "link": "javascript:fetch('https://example.com')" },
+                { "name": "App", "link": "steam://run/10" }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].name, "Rust");
+    }
+}
@@ -0,0 +1,343 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::{now, Bookmark, BookmarksDB, Group},
+    paths::{get_declared_path, Dirs},
+    settings::get_settings,
+    storage::StorageBackend,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeclaredFile {
+    #[serde(default)]
+    pub write_back: bool,
+    #[serde(default)]
+    pub bookmarks: Vec<DeclaredBookmark>,
+    #[serde(default)]
+    pub groups: Vec<DeclaredGroup>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeclaredBookmark {
+    pub name: String,
+    pub link: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub private: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeclaredGroup {
+    pub name: String,
+    #[serde(default)]
+    pub bookmarks: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub private: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+pub fn reconcile_declared(dirs: &Dirs, db: &mut BookmarksDB) -> Result<bool, String> {
+    let declared_path = get_declared_path(dirs);
+
+    if !declared_path.exists() {
+        return Ok(false);
+    }
+
+    let content = fs::read_to_string(&declared_path).map_err(|error| error.to_string())?;
+    let mut file = toml::from_str::<DeclaredFile>(&content).map_err(|error| error.to_string())?;
+    let original_db = db.to_owned();
+
+    for declared in &file.bookmarks {
+        reconcile_bookmark(db, declared);
+    }
+
+    let declared_names = file
+        .bookmarks
+        .iter()
+        .map(|declared| declared.name.to_lowercase())
+        .collect::<Vec<String>>();
+
+    let removed_bookmarks = db
+        .bookmarks
+        .iter()
+        .filter(|bookmark| {
+            bookmark.declared && !declared_names.contains(&bookmark.name.to_lowercase())
+        })
+        .map(|bookmark| bookmark.id.to_owned())
+        .collect::<Vec<String>>();
+
+    for bookmark_id in removed_bookmarks {
+        db.trash_bookmark(&bookmark_id);
+    }
+
+    for declared in &file.groups {
+        reconcile_group(db, declared);
+    }
+
+    let declared_groups = file
+        .groups
+        .iter()
+        .map(|declared| declared.name.to_lowercase())
+        .collect::<Vec<String>>();
+
+    let removed_groups = db
+        .groups
+        .iter()
+        .filter(|group| group.declared && !declared_groups.contains(&group.name.to_lowercase()))
+        .map(|group| group.id.to_owned())
+        .collect::<Vec<String>>();
+
+    for group_id in removed_groups {
+        db.trash_group(&group_id);
+    }
+
+    // The file is plain toml, so nothing from an encrypted db is written to it
+    let is_encrypted = get_settings(dirs).storage == StorageBackend::Encrypted;
+
+    let mut write_error = None;
+
+    if file.write_back && !is_encrypted {
+        let mut written_db = db.to_owned();
+
+        // The items only count as declared once the file has them
+        if write_back(&mut written_db, &mut file) {
+            match write_declared(&declared_path, &file) {
+                Ok(()) => *db = written_db,
+                Err(error) => write_error = Some(error),
+            }
+        }
+    }
+
+    let changed = *db != original_db;
+
+    if let Some(error) = write_error {
+        db.warnings
+            .push(format!("Error writing bookmarks.toml: {error}"));
+    }
+
+    Ok(changed)
+}
+
+fn write_declared(path: &Path, file: &DeclaredFile) -> Result<(), String> {
+    let content = toml::to_string_pretty(file).map_err(|error| error.to_string())?;
+    fs::write(path, content).map_err(|error| error.to_string())
+}
+
+fn reconcile_bookmark(db: &mut BookmarksDB, declared: &DeclaredBookmark) {
+    let existing = db
+        .bookmarks
+        .iter_mut()
+        .find(|bookmark| bookmark.name.to_lowercase() == declared.name.to_lowercase());
+
+    match existing {
+        Some(bookmark) => {
+            let matches = bookmark.name == declared.name
+                && bookmark.link == declared.link
+                && bookmark.tags == declared.tags
+                && bookmark.folder == declared.folder
+                && bookmark.private == declared.private
+                && bookmark.declared;
+
+            if !matches {
+                bookmark.name = declared.name.to_owned();
+                bookmark.link = declared.link.to_owned();
+                bookmark.tags = declared.tags.to_owned();
+                bookmark.folder = declared.folder.to_owned();
+                bookmark.private = declared.private;
+                bookmark.declared = true;
+                bookmark.updated_at = now();
+            }
+        }
+        None => db.bookmarks.push(Bookmark {
+            tags: declared.tags.to_owned(),
+            folder: declared.folder.to_owned(),
+            private: declared.private,
+            declared: true,
+            ..Bookmark::new(&declared.name, &declared.link)
+        }),
+    }
+}
+
+fn reconcile_group(db: &mut BookmarksDB, declared: &DeclaredGroup) {
+    let bookmarks_ids = declared
+        .bookmarks
+        .iter()
+        .filter_map(|name| {
+            db.bookmarks
+                .iter()
+                .find(|bookmark| bookmark.name.to_lowercase() == name.to_lowercase())
+        })
+        .map(|bookmark| bookmark.id.to_owned())
+        .collect::<Vec<String>>();
+
+    let existing = db
+        .groups
+        .iter_mut()
+        .find(|group| group.name.to_lowercase() == declared.name.to_lowercase());
+
+    match existing {
+        Some(group) => {
+            let matches = group.name == declared.name
+                && group.bookmarks_ids == bookmarks_ids
+                && group.private == declared.private
+                && group.declared;
+
+            if !matches {
+                group.name = declared.name.to_owned();
+                group.bookmarks_ids = bookmarks_ids;
+                group.private = declared.private;
+                group.declared = true;
+                group.updated_at = now();
+            }
+        }
+        None => db.groups.push(Group {
+            private: declared.private,
+            declared: true,
+            ..Group::new(&declared.name, &bookmarks_ids)
+        }),
+    }
+}
+
+fn write_back(db: &mut BookmarksDB, file: &mut DeclaredFile) -> bool {
+    let mut changed = false;
+
+    for bookmark in db
+        .bookmarks
+        .iter_mut()
+        .filter(|bookmark| !bookmark.declared)
+    {
+        file.bookmarks.push(DeclaredBookmark {
+            name: bookmark.name.to_owned(),
+            link: bookmark.link.to_owned(),
+            tags: bookmark.tags.to_owned(),
+            folder: bookmark.folder.to_owned(),
+            private: bookmark.private,
+        });
+
+        bookmark.declared = true;
+        changed = true;
+    }
+
+    for group in db.groups.iter_mut().filter(|group| !group.declared) {
+        let bookmarks = db
+            .bookmarks
+            .iter()
+            .filter(|bookmark| group.bookmarks_ids.contains(&bookmark.id))
+            .map(|bookmark| bookmark.name.to_owned())
+            .collect();
+
+        file.groups.push(DeclaredGroup {
+            name: group.name.to_owned(),
+            bookmarks,
+            private: group.private,
+        });
+
+        group.declared = true;
+        changed = true;
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::reconcile_declared;
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB},
+        paths::{get_declared_path, get_test_dirs},
+        store::{BookmarkStore, MemoryStore},
+    };
+
+    const DECLARED: &str = r#"
+        [[bookmarks]]
+        name = "Rust"
+        link = "https://rust-lang.org"
+        tags = ["lang"]
+
+        [[groups]]
+        name = "Work"
+        bookmarks = ["Rust"]
+    "#;
+
+    #[test]
+    fn the_file_wins_over_edits_in_the_db() {
+        let dirs = get_test_dirs();
+        fs::write(get_declared_path(&dirs), DECLARED).unwrap();
+
+        let mut db = BookmarksDB {
+            bookmarks: vec![Bookmark::new("Rust", "https://example.com")],
+            ..Default::default()
+        };
+
+        assert_eq!(reconcile_declared(&dirs, &mut db), Ok(true));
+
+        let bookmark = &db.bookmarks[0];
+
+        assert_eq!(bookmark.link, "https://rust-lang.org");
+        assert_eq!(bookmark.tags, vec!["lang"]);
+        assert!(bookmark.declared);
+        assert_eq!(db.groups[0].bookmarks_ids, vec![bookmark.id.to_owned()]);
+        assert_eq!(reconcile_declared(&dirs, &mut db), Ok(false));
+    }
+
+    #[test]
+    fn moves_what_was_removed_from_the_file_to_the_trash() {
+        let dirs = get_test_dirs();
+        let mut db = BookmarksDB::default();
+
+        fs::write(get_declared_path(&dirs), DECLARED).unwrap();
+        reconcile_declared(&dirs, &mut db).unwrap();
+
+        fs::write(get_declared_path(&dirs), "").unwrap();
+
+        assert_eq!(reconcile_declared(&dirs, &mut db), Ok(true));
+        assert!(db.bookmarks.is_empty());
+        assert!(db.groups.is_empty());
+        assert_eq!(db.trash.bookmarks[0].bookmark.name, "Rust");
+        assert_eq!(db.trash.groups[0].group.name, "Work");
+    }
+
+    #[test]
+    fn writes_the_items_added_in_tigris_back() {
+        let dirs = get_test_dirs();
+        let mut db = BookmarksDB {
+            bookmarks: vec![Bookmark::new("Docs", "https://docs.rs")],
+            ..Default::default()
+        };
+
+        fs::write(
+            get_declared_path(&dirs),
+            format!("write_back = true\n{DECLARED}"),
+        )
+        .unwrap();
+
+        assert_eq!(reconcile_declared(&dirs, &mut db), Ok(true));
+        assert!(db.bookmarks.iter().all(|bookmark| bookmark.declared));
+
+        let content = fs::read_to_string(get_declared_path(&dirs)).unwrap();
+
+        assert!(content.contains("https://docs.rs"));
+        assert!(content.contains("https://rust-lang.org"));
+    }
+
+    #[test]
+    fn shows_a_parse_error_as_a_warning() {
+        let dirs = get_test_dirs();
+        fs::write(get_declared_path(&dirs), "[[bookmarks]\n").unwrap();
+
+        let db = MemoryStore::new(&dirs, BookmarksDB::default()).get_db();
+
+        assert_eq!(db.warnings.len(), 1);
+        assert!(db.warnings[0].starts_with("Error in bookmarks.toml"));
+    }
+}
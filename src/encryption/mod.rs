@@ -0,0 +1,214 @@
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, OsRng, Payload},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
+use argon2::Argon2;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+pub const PASSPHRASE_VAR: &str = "TIGRIS_BOOKMARKS_PASSPHRASE";
+
+const MAGIC: &[u8; 4] = b"TBKE";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const STORE_ID_LENGTH: usize = 16;
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 1 + 4 + STORE_ID_LENGTH + SALT_LENGTH + NONCE_LENGTH;
+const KEYRING_SERVICE: &str = "tigris-bookmarks";
+
+pub type StoreId = [u8; STORE_ID_LENGTH];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    #[default]
+    Keyring,
+    Passphrase,
+}
+
+struct Header {
+    key_source: KeySource,
+    key_version: u32,
+    store_id: StoreId,
+    salt: [u8; SALT_LENGTH],
+    nonce: [u8; NONCE_LENGTH],
+}
+
+pub struct Cipher {
+    key_source: KeySource,
+    passphrase: Option<String>,
+}
+
+impl Cipher {
+    pub fn new(key_source: KeySource, passphrase: Option<String>) -> Self {
+        Self {
+            key_source,
+            passphrase,
+        }
+    }
+
+    pub fn encrypt(
+        &self,
+        plaintext: &[u8],
+        key_version: u32,
+        store_id: StoreId,
+    ) -> Result<Vec<u8>, String> {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+
+        let header = Header {
+            key_source: self.key_source,
+            key_version,
+            store_id,
+            salt,
+            nonce: Aes256Gcm::generate_nonce(&mut OsRng).into(),
+        };
+
+        let header_bytes = header.to_bytes();
+        let key = self.get_key(&header, true)?;
+
+        let ciphertext = Aes256Gcm::new(&key)
+            .encrypt(
+                Nonce::from_slice(&header.nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &header_bytes,
+                },
+            )
+            .map_err(|_| String::from("The db couldn't be encrypted"))?;
+
+        Ok([header_bytes, ciphertext].concat())
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let header = Header::from_bytes(data)?;
+        let key = self.get_key(&header, false)?;
+
+        Aes256Gcm::new(&key)
+            .decrypt(
+                Nonce::from_slice(&header.nonce),
+                Payload {
+                    msg: &data[HEADER_LENGTH..],
+                    aad: &data[..HEADER_LENGTH],
+                },
+            )
+            .map_err(|_| String::from("Wrong key or damaged file"))
+    }
+
+    fn get_key(&self, header: &Header, create: bool) -> Result<Key<Aes256Gcm>, String> {
+        match header.key_source {
+            KeySource::Passphrase => {
+                let passphrase = self.passphrase.as_ref().ok_or(format!(
+                    "Set the {PASSPHRASE_VAR} env var to use the encrypted db"
+                ))?;
+
+                let mut key = [0u8; 32];
+                Argon2::default()
+                    .hash_password_into(passphrase.as_bytes(), &header.salt, &mut key)
+                    .map_err(|error| error.to_string())?;
+
+                Ok(key.into())
+            }
+            KeySource::Keyring => {
+                let account = uuid::Uuid::from_bytes(header.store_id).to_string();
+
+                get_keyring_key(&account, header.key_version, create)
+            }
+        }
+    }
+}
+
+pub fn get_key_info(data: &[u8]) -> Option<(u32, StoreId)> {
+    Header::from_bytes(data)
+        .ok()
+        .map(|header| (header.key_version, header.store_id))
+}
+
+pub fn new_store_id() -> StoreId {
+    uuid::Uuid::new_v4().into_bytes()
+}
+
+fn get_keyring_key(
+    account: &str,
+    key_version: u32,
+    create: bool,
+) -> Result<Key<Aes256Gcm>, String> {
+    let user = format!("{account}#{key_version}");
+    let entry = Entry::new(KEYRING_SERVICE, &user).map_err(|error| error.to_string())?;
+
+    match entry.get_secret() {
+        Ok(secret) if secret.len() == 32 => Ok(*Key::<Aes256Gcm>::from_slice(&secret)),
+        Ok(_) => Err(String::from("The key in the keyring isn't valid")),
+        Err(keyring::Error::NoEntry) if create => {
+            let key = Aes256Gcm::generate_key(&mut OsRng);
+
+            entry.set_secret(&key).map_err(|error| error.to_string())?;
+
+            Ok(key)
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+impl Header {
+    fn to_bytes(&self) -> Vec<u8> {
+        let key_source = match self.key_source {
+            KeySource::Keyring => 0,
+            KeySource::Passphrase => 1,
+        };
+
+        [
+            MAGIC.as_slice(),
+            &[FORMAT_VERSION, key_source],
+            &self.key_version.to_le_bytes(),
+            &self.store_id,
+            &self.salt,
+            &self.nonce,
+        ]
+        .concat()
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() < HEADER_LENGTH || &data[..MAGIC.len()] != MAGIC {
+            return Err(String::from("The file isn't an encrypted db"));
+        }
+
+        let mut position = MAGIC.len();
+        let format_version = data[position];
+
+        if format_version != FORMAT_VERSION {
+            return Err(format!(
+                "The encrypted db uses format {format_version}, which this version can't read"
+            ));
+        }
+
+        let key_source = match data[position + 1] {
+            0 => KeySource::Keyring,
+            1 => KeySource::Passphrase,
+            _ => return Err(String::from("The encrypted db has an unknown key source")),
+        };
+
+        position += 2;
+
+        let key_version = u32::from_le_bytes(data[position..position + 4].try_into().unwrap());
+        position += 4;
+
+        let store_id = data[position..position + STORE_ID_LENGTH]
+            .try_into()
+            .unwrap();
+        position += STORE_ID_LENGTH;
+
+        let salt = data[position..position + SALT_LENGTH].try_into().unwrap();
+        position += SALT_LENGTH;
+
+        let nonce = data[position..position + NONCE_LENGTH].try_into().unwrap();
+
+        Ok(Self {
+            key_source,
+            key_version,
+            store_id,
+            salt,
+            nonce,
+        })
+    }
+}
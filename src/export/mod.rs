@@ -0,0 +1,65 @@
+use crate::bookmarks::BookmarksDB;
+
+pub fn to_json(db: &BookmarksDB) -> String {
+    let export = BookmarksDB {
+        bookmarks: db.bookmarks.to_owned(),
+        groups: db.groups.to_owned(),
+        ..BookmarksDB::default()
+    };
+
+    serde_json::to_string_pretty(&export).expect("Error serializing bookmarks")
+}
+
+pub fn to_bookmarks_html(db: &BookmarksDB) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+        <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+        <TITLE>Bookmarks</TITLE>\n\
+        <H1>Bookmarks</H1>\n\
+        <DL><p>\n",
+    );
+
+    for bookmark in &db.bookmarks {
+        html.push_str(&format!(
+            "    <DT><A HREF=\"{}\" ADD_DATE=\"{}\" LAST_MODIFIED=\"{}\">{}</A>\n",
+            encode_entities(&bookmark.link),
+            bookmark.created_at,
+            bookmark.updated_at,
+            encode_entities(&bookmark.name)
+        ));
+    }
+
+    for group in &db.groups {
+        html.push_str(&format!(
+            "    <DT><H3 ADD_DATE=\"{}\">{}</H3>\n    <DL><p>\n",
+            group.created_at,
+            encode_entities(&group.name)
+        ));
+
+        for bookmark in db
+            .bookmarks
+            .iter()
+            .filter(|bookmark| group.bookmarks_ids.contains(&bookmark.id))
+        {
+            html.push_str(&format!(
+                "        <DT><A HREF=\"{}\" ADD_DATE=\"{}\">{}</A>\n",
+                encode_entities(&bookmark.link),
+                bookmark.created_at,
+                encode_entities(&bookmark.name)
+            ));
+        }
+
+        html.push_str("    </DL><p>\n");
+    }
+
+    html.push_str("</DL><p>\n");
+
+    html
+}
+
+pub fn encode_entities(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
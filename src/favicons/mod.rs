@@ -0,0 +1,79 @@
+use std::{fs, io::Cursor};
+
+use image::{ImageFormat, ImageReader};
+use reqwest::Client;
+
+use crate::{
+    bookmarks::Bookmark,
+    paths::{get_favicon_path, get_favicons_dir, Dirs},
+};
+
+pub async fn write_favicon(dirs: &Dirs, bookmark: &Bookmark) {
+    if bookmark.private {
+        remove_favicon(dirs, &bookmark.id);
+        return;
+    }
+
+    let website = bookmark.link.replace("https://", "").replace("http://", "");
+
+    let favicon_request = Client::new()
+        .get(format!("https://favicon.is/{website}?larger=true"))
+        .send()
+        .await;
+
+    if let Ok(response) = favicon_request {
+        if response.status().is_success() {
+            let bytes = response.bytes().await.unwrap();
+
+            if !get_favicons_dir(dirs).exists() {
+                fs::create_dir_all(get_favicons_dir(dirs))
+                    .expect("Error creating favicons directory");
+            }
+
+            let favicon_path = get_favicon_path(dirs, &bookmark.id);
+
+            let image = ImageReader::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .unwrap()
+                .decode()
+                .expect("Error converting bytes to image");
+
+            image
+                .save_with_format(&favicon_path, ImageFormat::Png)
+                .expect("Error saving image");
+        }
+    }
+}
+
+pub async fn write_favicons(dirs: &Dirs, bookmarks: &[Bookmark]) {
+    let favicon_tasks = bookmarks
+        .iter()
+        .map(|bookmark| {
+            let dirs = dirs.to_owned();
+            let bookmark = bookmark.to_owned();
+
+            tokio::spawn(async move { write_favicon(&dirs, &bookmark).await })
+        })
+        .collect::<Vec<_>>();
+
+    for task in favicon_tasks {
+        let _ = task.await;
+    }
+}
+
+pub fn rename_favicons(dirs: &Dirs, renamed_ids: &[(String, String)]) {
+    for (old_id, new_id) in renamed_ids {
+        let _ = fs::rename(
+            get_favicon_path(dirs, old_id),
+            get_favicon_path(dirs, new_id),
+        );
+    }
+}
+
+pub fn remove_favicon(dirs: &Dirs, bookmark_id: &str) {
+    let favicon_path = get_favicon_path(dirs, bookmark_id);
+
+    if favicon_path.exists() {
+        fs::remove_file(favicon_path).expect("Error deleting favicon");
+    }
+}
@@ -1,159 +1,275 @@
-use std::{fs, io::Cursor, process::exit};
+use std::{fs, process::exit};
 
-use image::{ImageFormat, ImageReader};
-use regex::Regex;
-use reqwest::Client;
 use tigris_core::features::{api::FormResultsRequest, utils::send_notification};
 
 use crate::{
-    bookmarks::{get_db, write_db, Bookmark, Group},
-    paths::{get_favicon_path, get_favicons_dir},
+    bookmarks::{
+        is_id, is_valid_link, now, parse_tags, Bookmark, Group, LaunchOptions, ReadLaterItem,
+    },
+    collections::{refresh_collections, remove_collection_cache},
+    favicons::{write_favicon, write_favicons},
+    import::{import_bookmarks, parse_bookmarks_html},
+    launch::open_group,
+    pages::fetch_page,
+    profiles::{create_profile, switch_profile},
+    rules::Rule,
+    settings::{get_settings, write_settings, CollectionSource},
+    storage::{check_unencrypted_copies, StorageBackend},
+    store::BookmarkStore,
 };
 
-pub async fn handle_forms(request: FormResultsRequest) {
+const DECLARED_MESSAGE: &str = "It's declared in bookmarks.toml, remove it from the file instead";
+
+pub async fn handle_forms(request: FormResultsRequest, store: &dyn BookmarkStore) {
+    let succeeded = submit_form(request, store).await;
+
+    exit(if succeeded { 0 } else { 1 });
+}
+
+async fn submit_form(request: FormResultsRequest, store: &dyn BookmarkStore) -> bool {
     let form_id = &request.form_id;
+    let dirs = store.dirs();
 
     match form_id.as_str() {
         "add-bookmark" => {
-            let name = &request.get_string_value("name").unwrap();
+            let name = request.get_string_value("name").unwrap().trim().to_owned();
             let link = &request.get_string_value("link").unwrap();
 
-            if !is_valid_link(&link) {
+            if !is_valid_link(link) {
                 send_notification("Invalid Link", "The provided link isn't a valid link");
-                exit(1);
+                return false;
             }
 
-            let bookmark = Bookmark::new(name, link);
-            let mut db = get_db();
+            let name = &match name.is_empty() {
+                true => fetch_page(link)
+                    .await
+                    .ok()
+                    .and_then(|page| page.title)
+                    .unwrap_or(link.to_owned()),
+                false => name,
+            };
 
-            write_favicon(&bookmark.id, &bookmark.link).await;
+            let bookmark = Bookmark {
+                private: request.get_bool_value("private").unwrap_or(false),
+                ..Bookmark::new(name, link)
+            };
 
-            db.bookmarks.push(bookmark);
+            write_favicon(dirs, &bookmark).await;
 
-            write_db(&db);
+            store.insert(&bookmark);
 
             send_notification("Success", &format!("{name} added successfully"));
-            exit(0)
+            true
         }
-        "delete-bookmark" => {
-            let bookmark_id = request.get_usize_value("bookmark").unwrap();
-            let mut db = get_db();
-
-            db.bookmarks = db
-                .bookmarks
-                .iter()
-                .filter(|bookmark| bookmark.id != bookmark_id)
-                .map(|bookmark| bookmark.to_owned())
-                .collect();
-
-            db.groups = db
-                .groups
-                .iter()
-                .map(|group| {
-                    let mut group = group.to_owned();
-
-                    if group.bookmarks_ids.contains(&bookmark_id) {
-                        group.bookmarks_ids = group
-                            .bookmarks_ids
-                            .iter()
-                            .map(|id| id.to_owned())
-                            .filter(|id| id != &bookmark_id)
-                            .collect();
-                    }
+        "add-read-later" => {
+            let link = request.get_string_value("link").unwrap().trim().to_owned();
 
-                    group
-                })
-                .collect();
-
-            write_db(&db);
+            if !is_valid_link(&link) {
+                send_notification("Invalid Link", "The provided link isn't a valid link");
+                return false;
+            }
 
-            let favicon_path = get_favicon_path(&bookmark_id.to_string());
+            // The item is still added when the page can't be downloaded, just without a title
+            let page = fetch_page(&link).await.ok();
+            let title = page
+                .as_ref()
+                .and_then(|page| page.title.to_owned())
+                .unwrap_or(link.to_owned());
+
+            let item = ReadLaterItem::new(
+                &title,
+                &link,
+                page.as_ref().map(|page| page.reading_minutes),
+            );
+
+            let mut db = store.get_db();
+            db.read_later.push(item);
+            store.write_db(&db);
+
+            send_notification("Success", &format!("{title} added to read later"));
+            true
+        }
+        "delete-bookmark" => {
+            let bookmark_id = request.args.first().unwrap().to_owned();
+
+            if store
+                .get(&bookmark_id)
+                .is_some_and(|bookmark| bookmark.declared)
+            {
+                send_notification("Not Deleted", DECLARED_MESSAGE);
+                return false;
+            }
 
-            if favicon_path.exists() {
-                fs::remove_file(favicon_path).expect("Error deleting favicon");
+            if store.delete(&bookmark_id).is_none() {
+                send_notification("Invalid Bookmark", "The bookmark doesn't exist anymore");
+                return false;
             }
 
-            send_notification("Success", "Bookmark deleted successfully");
+            send_notification("Success", "Bookmark moved to the trash");
 
-            exit(0);
+            true
         }
         "edit-bookmark" => {
             let args = &request.args;
-            let bookmark_id = args.get(0).unwrap().parse::<usize>().unwrap();
-            let mut db = get_db();
+            let bookmark_id = args.first().unwrap().to_owned();
             let name = &request.get_string_value("name").unwrap();
             let link = &request.get_string_value("link").unwrap();
+            let tags = parse_tags(&request.get_string_value("tags").unwrap_or_default());
+            let folder = request.get_string_value("folder").unwrap_or_default();
 
-            if !is_valid_link(&link) {
+            if !is_valid_link(link) {
                 send_notification("Invalid Link", "The provided link isn't a valid link");
-                exit(1);
+                return false;
             }
 
-            db.bookmarks = db
-                .bookmarks
-                .iter()
-                .map(|bookmark| {
-                    if &bookmark.id == &bookmark_id {
-                        Bookmark {
-                            id: bookmark_id.to_owned(),
-                            name: name.to_owned(),
-                            link: link.to_owned(),
-                        }
-                    } else {
-                        bookmark.to_owned()
-                    }
-                })
-                .collect();
+            let bookmark = match store.get(&bookmark_id) {
+                Some(bookmark) => bookmark,
+                None => {
+                    send_notification("Invalid Bookmark", "The bookmark doesn't exist anymore");
+                    return false;
+                }
+            };
+
+            let bookmark = Bookmark {
+                name: name.to_owned(),
+                link: link.to_owned(),
+                tags,
+                folder: parse_folder(&folder),
+                private: request.get_bool_value("private").unwrap_or(false),
+                updated_at: now(),
+                ..bookmark
+            };
 
-            write_favicon(&bookmark_id, &link).await;
+            write_favicon(dirs, &bookmark).await;
 
-            write_db(&db);
+            store.update(&bookmark);
 
             send_notification("Success", &format!("{name} edited successfully"));
-            exit(0);
+            true
         }
         "add-group" => {
-            let results = &request.results;
             let name = &request.get_string_value("name").unwrap();
-            let selected_bookmarks = results
-                .iter()
-                .filter(|result| request.get_bool_value(&result.id).unwrap())
-                .map(|result| result.id.parse::<usize>().unwrap())
-                .collect::<Vec<usize>>();
-
-            let group = Group::new(name, &selected_bookmarks);
+            let filter = request.get_string_value("filter").unwrap_or_default();
+
+            let launch = match parse_launch_options(&request) {
+                Ok(launch) => launch,
+                Err(error) => {
+                    send_notification("Invalid Launch Options", &error);
+                    return false;
+                }
+            };
+
+            let rule = match parse_rule(&request) {
+                Ok(rule) => rule,
+                Err(error) => {
+                    send_notification("Invalid Rule", &error);
+                    return false;
+                }
+            };
+
+            let mut db = store.get_db();
+            let is_private = request.get_bool_value("private").unwrap_or(false);
+
+            // Starts with the bookmarks matching the filter, the rest are added with the g keyword
+            let bookmarks_ids = match filter.trim().is_empty() {
+                true => vec![],
+                false => db
+                    .bookmarks
+                    .iter()
+                    .filter(|bookmark| is_private || !bookmark.private)
+                    .filter(|bookmark| bookmark.matches_words(&filter))
+                    .map(|bookmark| bookmark.id.to_owned())
+                    .collect(),
+            };
+
+            // A new group can't be inside anything yet, so it can't make a cycle
+            let group = Group {
+                groups_ids: get_selected_groups(&request),
+                rule,
+                private: is_private,
+                launch,
+                ..Group::new(name, &bookmarks_ids)
+            };
 
-            let mut db = get_db();
             db.groups.push(group);
 
-            write_db(&db);
+            store.write_db(&db);
 
             send_notification("Success", &format!("{} added successfully", name));
 
-            exit(0);
+            true
         }
         "edit-group" => {
             let args = &request.args;
-            let group_id = args.get(0).unwrap().parse::<usize>().unwrap();
+            let group_id = args.first().unwrap().to_owned();
             let name = &request.get_string_value("name").unwrap();
 
-            let mut db = get_db();
-            let selected_bookmarks = &request
-                .results
+            let launch = match parse_launch_options(&request) {
+                Ok(launch) => launch,
+                Err(error) => {
+                    send_notification("Invalid Launch Options", &error);
+                    return false;
+                }
+            };
+
+            let rule = match parse_rule(&request) {
+                Ok(rule) => rule,
+                Err(error) => {
+                    send_notification("Invalid Rule", &error);
+                    return false;
+                }
+            };
+
+            let mut db = store.get_db();
+            let selected_groups = &get_selected_groups(&request);
+
+            let private_groups = db
+                .groups
                 .iter()
-                .filter(|result| request.get_bool_value(&result.id).unwrap())
-                .map(|result| result.id.parse::<usize>().unwrap())
-                .collect::<Vec<usize>>();
+                .filter(|group| group.private)
+                .map(|group| group.id.to_owned())
+                .collect::<Vec<String>>();
+
+            let is_private = request.get_bool_value("private").unwrap_or(false);
+
+            let mut groups_ids = selected_groups.to_owned();
+
+            // The form only lists private items in private groups, so the ones it didn't show
+            // stay in the group
+            if let Some(group) = db.groups.iter().find(|group| group.id == group_id) {
+                if !group.private {
+                    groups_ids.extend(
+                        group
+                            .groups_ids
+                            .iter()
+                            .filter(|id| private_groups.contains(id))
+                            .cloned(),
+                    );
+                }
+            }
+
+            if db.creates_cycle(&group_id, &groups_ids) {
+                send_notification(
+                    "Invalid Group",
+                    &format!("{name} can't be inside a group that's inside it"),
+                );
+                return false;
+            }
 
             db.groups = db
                 .groups
                 .iter()
                 .map(|group| {
                     if group.id == group_id {
+                        // The bookmarks are added and removed with the g keyword
                         Group {
-                            id: group.id,
                             name: name.to_owned(),
-                            bookmarks_ids: selected_bookmarks.to_owned(),
+                            groups_ids: groups_ids.to_owned(),
+                            rule: rule.to_owned(),
+                            private: is_private,
+                            launch: launch.to_owned(),
+                            updated_at: now(),
+                            ..group.to_owned()
                         }
                     } else {
                         group.to_owned()
@@ -161,68 +277,536 @@ pub async fn handle_forms(request: FormResultsRequest) {
                 })
                 .collect();
 
-            write_db(&db);
+            store.write_db(&db);
 
             send_notification("Success", &format!("{name} edited successfully"));
 
-            exit(0)
+            true
+        }
+        "open-group" => {
+            let group_id = request.args.first().unwrap().to_owned();
+            let selected_bookmarks = get_selected_bookmarks(&request);
+            let mut db = store.get_db();
+
+            if let Err(error) = open_group(&mut db, &group_id, Some(&selected_bookmarks)) {
+                send_notification("Error Opening Group", &error);
+                return false;
+            }
+
+            store.write_db(&db);
+
+            true
         }
         "delete-group" => {
-            let group_id = request.get_usize_value("group").unwrap();
-            let mut db = get_db();
+            let group_id = request.args.first().unwrap().to_owned();
+            let mut db = store.get_db();
 
-            db.groups = db
+            if db
                 .groups
                 .iter()
-                .filter(|group| group.id != group_id)
-                .map(|group| group.to_owned())
-                .collect();
+                .any(|group| group.id == group_id && group.declared)
+            {
+                send_notification("Not Deleted", DECLARED_MESSAGE);
+                return false;
+            }
+
+            if db.trash_group(&group_id).is_none() {
+                send_notification("Invalid Group", "The group doesn't exist anymore");
+                return false;
+            }
 
-            write_db(&db);
+            store.write_db(&db);
 
-            send_notification("Success", "Group deleted successfully");
+            send_notification("Success", "Group moved to the trash");
 
-            exit(0);
+            true
         }
-        _ => {}
-    }
-}
+        "bulk-delete" => {
+            let selected_bookmarks = get_selected_bookmarks(&request);
+            let mut db = store.get_db();
+
+            let (declared_ids, deleted_ids): (Vec<String>, Vec<String>) =
+                selected_bookmarks.into_iter().partition(|bookmark_id| {
+                    db.bookmarks
+                        .iter()
+                        .any(|bookmark| &bookmark.id == bookmark_id && bookmark.declared)
+                });
+
+            let deleted_count = deleted_ids
+                .iter()
+                .filter_map(|bookmark_id| db.trash_bookmark(bookmark_id))
+                .count();
+
+            store.write_db(&db);
+
+            let mut message = format!("{deleted_count} bookmarks moved to the trash");
+
+            if !declared_ids.is_empty() {
+                message += &format!(", {} are declared in bookmarks.toml", declared_ids.len());
+            }
+
+            send_notification("Success", &message);
+
+            true
+        }
+        "bulk-tag" => {
+            let tags = parse_tags(&request.get_string_value("tags").unwrap());
+            let selected_bookmarks = get_selected_bookmarks(&request);
+            let mut db = store.get_db();
+
+            if tags.is_empty() {
+                send_notification("Invalid Tags", "Type at least one tag");
+                return false;
+            }
+
+            let mut tagged_count = 0;
 
-async fn write_favicon(id: &usize, link: &str) {
-    let website = link.replace("https://", "").replace("http://", "");
+            for bookmark in db
+                .bookmarks
+                .iter_mut()
+                .filter(|bookmark| selected_bookmarks.contains(&bookmark.id))
+            {
+                bookmark.add_tags(&tags);
+                bookmark.updated_at = now();
+                tagged_count += 1;
+            }
 
-    let favicon_request = Client::new()
-        .get(format!("https://favicon.is/{website}?larger=true"))
-        .send()
-        .await;
+            store.write_db(&db);
 
-    if let Ok(response) = favicon_request {
-        if response.status().is_success() {
-            let bytes = response.bytes().await.unwrap();
+            send_notification(
+                "Success",
+                &format!("Tagged {tagged_count} bookmarks with {}", tags.join(", ")),
+            );
 
-            if !get_favicons_dir().exists() {
-                fs::create_dir_all(get_favicons_dir()).expect("Error creating favicons directory");
+            true
+        }
+        "bulk-add-to-group" | "bulk-remove-from-group" => {
+            let group_id = request.get_string_value("group").unwrap();
+            let selected_bookmarks = get_selected_bookmarks(&request);
+            let adding = form_id == "bulk-add-to-group";
+            let mut db = store.get_db();
+
+            let group = match db.groups.iter_mut().find(|group| group.id == group_id) {
+                Some(group) => group,
+                None => {
+                    send_notification("Invalid Group", "The group doesn't exist anymore");
+                    return false;
+                }
+            };
+
+            let mut changed_count = 0;
+
+            for bookmark_id in &selected_bookmarks {
+                let in_group = group.bookmarks_ids.contains(bookmark_id);
+
+                if adding && !in_group {
+                    group.bookmarks_ids.push(bookmark_id.to_owned());
+                    changed_count += 1;
+                }
+
+                if !adding && in_group {
+                    group.bookmarks_ids.retain(|id| id != bookmark_id);
+                    changed_count += 1;
+                }
             }
 
-            let favicon_path = get_favicon_path(&id.to_string());
+            group.updated_at = now();
+
+            let message = if adding {
+                format!("Added {changed_count} bookmarks to {}", group.name)
+            } else {
+                format!("Removed {changed_count} bookmarks from {}", group.name)
+            };
+
+            store.write_db(&db);
+
+            send_notification("Success", &message);
 
-            let image = ImageReader::new(Cursor::new(&bytes))
-                .with_guessed_format()
+            true
+        }
+        "add-bookmark-to-group" => {
+            let bookmark_id = request.args.first().unwrap().to_owned();
+            let group_id = request.get_string_value("group").unwrap();
+            let mut db = store.get_db();
+
+            let bookmark_name = match db
+                .bookmarks
+                .iter()
+                .find(|bookmark| bookmark.id == bookmark_id)
+            {
+                Some(bookmark) => bookmark.name.to_owned(),
+                None => {
+                    send_notification("Invalid Bookmark", "The bookmark doesn't exist anymore");
+                    return false;
+                }
+            };
+
+            let group = match db.groups.iter_mut().find(|group| group.id == group_id) {
+                Some(group) => group,
+                None => {
+                    send_notification("Invalid Group", "The group doesn't exist anymore");
+                    return false;
+                }
+            };
+
+            if !group.bookmarks_ids.contains(&bookmark_id) {
+                group.bookmarks_ids.push(bookmark_id);
+                group.updated_at = now();
+            }
+
+            let message = format!("{bookmark_name} added to {}", group.name);
+
+            store.write_db(&db);
+
+            send_notification("Success", &message);
+
+            true
+        }
+        "bulk-move" => {
+            let folder = parse_folder(&request.get_string_value("folder").unwrap());
+            let selected_bookmarks = get_selected_bookmarks(&request);
+            let mut db = store.get_db();
+            let mut moved_count = 0;
+
+            for bookmark in db
+                .bookmarks
+                .iter_mut()
+                .filter(|bookmark| selected_bookmarks.contains(&bookmark.id))
+            {
+                bookmark.folder = folder.to_owned();
+                bookmark.updated_at = now();
+                moved_count += 1;
+            }
+
+            store.write_db(&db);
+
+            let message = match &folder {
+                Some(folder) => format!("Moved {moved_count} bookmarks to {folder}"),
+                None => format!("Removed {moved_count} bookmarks from their folder"),
+            };
+
+            send_notification("Success", &message);
+
+            true
+        }
+        "add-collection" => {
+            let name = request.get_string_value("name").unwrap().trim().to_owned();
+            let source = request
+                .get_string_value("source")
                 .unwrap()
-                .decode()
-                .expect("Error converting bytes to image");
+                .trim()
+                .to_owned();
+            let refresh_minutes = match request
+                .get_string_value("refresh_minutes")
+                .unwrap_or_default()
+                .trim()
+                .parse::<u64>()
+            {
+                Ok(minutes) => minutes,
+                Err(_) => {
+                    send_notification("Invalid Value", "The refresh minutes must be a number");
+                    return false;
+                }
+            };
+
+            let mut settings = get_settings(dirs);
+
+            if settings
+                .collections
+                .iter()
+                .any(|collection| collection.name == name)
+            {
+                send_notification(
+                    "Invalid Name",
+                    "There's already a collection with that name",
+                );
+                return false;
+            }
+
+            settings.collections.push(CollectionSource {
+                name: name.to_owned(),
+                source,
+                refresh_minutes,
+            });
+
+            write_settings(dirs, &settings);
+
+            let message = match refresh_collections(dirs, false)
+                .await
+                .into_iter()
+                .find(|(collection_name, _)| collection_name == &name)
+            {
+                Some((_, Err(error))) => {
+                    format!("Subscribed to {name}, but it couldn't be downloaded: {error}")
+                }
+                Some((_, Ok(count))) => format!("Subscribed to {name} with {count} bookmarks"),
+                None => format!("Subscribed to {name}"),
+            };
+
+            send_notification("Success", &message);
+
+            true
+        }
+        "remove-collection" => {
+            let name = request.get_string_value("collection").unwrap();
+            let mut settings = get_settings(dirs);
+
+            settings
+                .collections
+                .retain(|collection| collection.name != name);
+
+            write_settings(dirs, &settings);
+
+            remove_collection_cache(dirs, &name);
+
+            send_notification("Success", &format!("Unsubscribed from {name}"));
+
+            true
+        }
+        "edit-settings" => {
+            let trash_days = match request
+                .get_string_value("trash_days")
+                .unwrap()
+                .trim()
+                .parse::<u64>()
+            {
+                Ok(days) => days,
+                Err(_) => {
+                    send_notification("Invalid Value", "The trash days must be a number");
+                    return false;
+                }
+            };
+
+            let storage = match request.get_string_value("storage").as_deref() {
+                Some("sqlite") => StorageBackend::Sqlite,
+                Some("encrypted") => StorageBackend::Encrypted,
+                _ => StorageBackend::Json,
+            };
+
+            let mut settings = get_settings(dirs);
+
+            if storage == StorageBackend::Encrypted && settings.storage != storage {
+                if let Err(error) = check_unencrypted_copies(dirs) {
+                    send_notification("Not Saved", &error);
+                    return false;
+                }
+            }
+
+            settings.trash_days = trash_days;
+            settings.storage = storage;
+            settings.git_sync = request.get_bool_value("git_sync").unwrap_or(false);
+            settings.git_remote = request
+                .get_string_value("git_remote")
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+            settings.http_sync_url = request
+                .get_string_value("http_sync_url")
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+
+            write_settings(dirs, &settings);
+
+            send_notification("Success", "Settings saved successfully");
+
+            true
+        }
+        "switch-profile" => {
+            let profile = request.get_string_value("profile").unwrap();
+
+            if let Err(error) = switch_profile(&dirs.data_dir, &profile) {
+                send_notification("Invalid Profile", &error);
+                return false;
+            }
+
+            send_notification("Success", &format!("Switched to the {profile} profile"));
+
+            true
+        }
+        "add-profile" => {
+            let name = request.get_string_value("name").unwrap().trim().to_owned();
+
+            if let Err(error) = create_profile(&dirs.data_dir, &name)
+                .and_then(|_| switch_profile(&dirs.data_dir, &name))
+            {
+                send_notification("Invalid Profile", &error);
+                return false;
+            }
+
+            send_notification("Success", &format!("Switched to the new {name} profile"));
+
+            true
+        }
+        "import-bookmarks" => {
+            let path = &request.get_string_value("path").unwrap();
+
+            let html = match fs::read_to_string(path) {
+                Ok(html) => html,
+                Err(_) => {
+                    send_notification("Invalid File", "The bookmarks file couldn't be read");
+                    return false;
+                }
+            };
+
+            let mut db = store.get_db();
+            let imported = import_bookmarks(&mut db, parse_bookmarks_html(&html));
+
+            write_favicons(dirs, &imported).await;
+
+            let imported_count = imported.len();
+
+            store.write_db(&db);
+
+            send_notification(
+                "Success",
+                &format!("{imported_count} bookmarks imported successfully"),
+            );
 
-            image
-                .save_with_format(&favicon_path, ImageFormat::Png)
-                .expect("Error saving image");
+            true
         }
+        _ => false,
     }
 }
 
-fn is_valid_link(link: &str) -> bool {
-    let url_regex = Regex::new(
-        r"https?:\/\/(www\.)?[-a-zA-Z0-9@:%._\+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b([-a-zA-Z0-9()@:%_\+.~#?&//=]*)",
-    ).unwrap();
+fn get_selected_bookmarks(request: &FormResultsRequest) -> Vec<String> {
+    request
+        .results
+        .iter()
+        .filter(|result| is_id(&result.id) && request.get_bool_value(&result.id).unwrap_or(false))
+        .map(|result| result.id.to_owned())
+        .collect()
+}
+
+fn parse_launch_options(request: &FormResultsRequest) -> Result<LaunchOptions, String> {
+    let get_text = |id: &str| request.get_string_value(id).unwrap_or_default();
+
+    let parse_count = |id: &str, label: &str| {
+        let text = get_text(id);
+
+        match text.trim() {
+            "" => Ok(None),
+            count => count
+                .parse::<usize>()
+                .map(Some)
+                .map_err(|_| format!("{label} needs a whole number")),
+        }
+    };
+
+    let delay_seconds = match get_text("delay").trim() {
+        "" => 0.0,
+        delay => delay
+            .parse::<f64>()
+            .ok()
+            .filter(|delay| *delay >= 0.0)
+            .ok_or(String::from("The delay needs a number of seconds"))?,
+    };
+
+    let browser = get_text("browser").trim().to_owned();
+
+    Ok(LaunchOptions {
+        delay_ms: (delay_seconds * 1000.0).round() as u64,
+        max_tabs: parse_count("max_tabs", "Max tabs")?,
+        confirm_above: parse_count("confirm_above", "Confirm above")?,
+        browser: (!browser.is_empty()).then_some(browser),
+    })
+}
+
+fn get_selected_groups(request: &FormResultsRequest) -> Vec<String> {
+    request
+        .results
+        .iter()
+        .filter_map(|result| result.id.strip_prefix("group:"))
+        .filter(|id| {
+            request
+                .get_bool_value(&format!("group:{id}"))
+                .unwrap_or(false)
+        })
+        .map(|id| id.to_owned())
+        .collect()
+}
+
+fn parse_rule(request: &FormResultsRequest) -> Result<Option<String>, String> {
+    let rule = request.get_string_value("rule").unwrap_or_default();
+    let rule = rule.trim();
 
-    url_regex.is_match(&link)
+    if rule.is_empty() {
+        return Ok(None);
+    }
+
+    Rule::parse(rule)?;
+
+    Ok(Some(rule.to_owned()))
+}
+
+fn parse_folder(text: &str) -> Option<String> {
+    let folder = text.trim();
+
+    if folder.is_empty() {
+        None
+    } else {
+        Some(folder.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tigris_core::features::api::FormResultsRequest;
+
+    use super::submit_form;
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB, Group},
+        paths::get_test_dirs,
+        store::{BookmarkStore, MemoryStore},
+    };
+
+    fn request(form_id: &str, args: &[&str]) -> FormResultsRequest {
+        FormResultsRequest {
+            form_id: form_id.to_owned(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            results: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_bookmark_moves_it_to_the_trash() {
+        let bookmark = Bookmark::new("Docs", "https://docs.rs");
+        let db = BookmarksDB {
+            bookmarks: vec![bookmark.to_owned()],
+            ..Default::default()
+        };
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, db);
+
+        assert!(submit_form(request("delete-bookmark", &[&bookmark.id]), &store).await);
+
+        let db = store.get_db();
+        assert!(db.bookmarks.is_empty());
+        assert_eq!(db.trash.bookmarks[0].bookmark.id, bookmark.id);
+    }
+
+    #[tokio::test]
+    async fn delete_bookmark_rejects_a_missing_bookmark() {
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, BookmarksDB::default());
+
+        assert!(!submit_form(request("delete-bookmark", &["missing"]), &store).await);
+    }
+
+    #[tokio::test]
+    async fn delete_group_moves_it_to_the_trash() {
+        let group = Group::new("Work", &vec![]);
+        let db = BookmarksDB {
+            groups: vec![group.to_owned()],
+            ..Default::default()
+        };
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, db);
+
+        assert!(submit_form(request("delete-group", &[&group.id]), &store).await);
+
+        let db = store.get_db();
+        assert!(db.groups.is_empty());
+        assert_eq!(db.trash.groups[0].group.id, group.id);
+    }
 }
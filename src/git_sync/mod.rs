@@ -0,0 +1,230 @@
+use std::{fs, path::Path, process::Command};
+
+use crate::{
+    bookmarks::BookmarksDB,
+    merge::merge_dbs,
+    paths::{get_git_sync_dir, Dirs},
+    settings::{get_settings, Settings},
+    sync_state::{record_sync_error, record_sync_success},
+};
+
+const DB_FILE: &str = "db.json";
+
+pub fn sync_with_git(dirs: &Dirs, db: &BookmarksDB) -> Result<Option<BookmarksDB>, String> {
+    let settings = get_settings(dirs);
+
+    if !settings.git_sync {
+        return Ok(None);
+    }
+
+    match pull_and_push(dirs, &settings, db) {
+        Ok(merged) => {
+            record_sync_success(dirs);
+            Ok(merged)
+        }
+        Err(error) => {
+            record_sync_error(dirs, &error);
+            Err(error)
+        }
+    }
+}
+
+fn pull_and_push(
+    dirs: &Dirs,
+    settings: &Settings,
+    db: &BookmarksDB,
+) -> Result<Option<BookmarksDB>, String> {
+    let sync_dir = &get_git_sync_dir(dirs);
+
+    init_repo(sync_dir, settings)?;
+    commit_db(sync_dir, db, "Update bookmarks")?;
+
+    if settings.git_remote.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let branch = &settings.git_branch;
+    let remote_branch = format!("origin/{branch}");
+
+    git(sync_dir, &["fetch", "origin"])?;
+
+    // The remote branch doesn't exist until the first push
+    if git(
+        sync_dir,
+        &["rev-parse", "--verify", "--quiet", &remote_branch],
+    )
+    .is_err()
+    {
+        git(sync_dir, &["push", "origin", &format!("HEAD:{branch}")])?;
+        return Ok(None);
+    }
+
+    if is_ancestor(sync_dir, &remote_branch, "HEAD") {
+        git(sync_dir, &["push", "origin", &format!("HEAD:{branch}")])?;
+        return Ok(None);
+    }
+
+    if is_ancestor(sync_dir, "HEAD", &remote_branch) {
+        git(sync_dir, &["merge", "--ff-only", &remote_branch])?;
+        return Ok(Some(read_db_at(sync_dir, "HEAD")?));
+    }
+
+    // A machine that started syncing after having bookmarks has its own history
+    let base = match git(sync_dir, &["merge-base", "HEAD", &remote_branch]) {
+        Ok(merge_base) => read_db_at(sync_dir, merge_base.trim()).unwrap_or_default(),
+        Err(_) => BookmarksDB::default(),
+    };
+
+    let theirs = read_db_at(sync_dir, &remote_branch)?;
+    let merged = merge_dbs(&base, db, &theirs);
+
+    // Record the merge with both parents but let the field level merge decide the content
+    git(
+        sync_dir,
+        &[
+            "merge",
+            "--no-commit",
+            "--allow-unrelated-histories",
+            "-s",
+            "ours",
+            &remote_branch,
+        ],
+    )?;
+    write_repo_db(sync_dir, &merged)?;
+    git(sync_dir, &["add", DB_FILE])?;
+    git(sync_dir, &["commit", "-m", "Merge bookmarks"])?;
+    git(sync_dir, &["push", "origin", &format!("HEAD:{branch}")])?;
+
+    Ok(Some(merged))
+}
+
+fn init_repo(sync_dir: &Path, settings: &Settings) -> Result<(), String> {
+    if !sync_dir.join(".git").exists() {
+        fs::create_dir_all(sync_dir).map_err(|error| error.to_string())?;
+        git(
+            sync_dir,
+            &["init", "--initial-branch", &settings.git_branch],
+        )?;
+    }
+
+    // Commits and merges fail on machines without a global git identity
+    if git(sync_dir, &["config", "user.email"]).is_err() {
+        git(sync_dir, &["config", "user.name", "tigris-bookmarks"])?;
+        git(
+            sync_dir,
+            &["config", "user.email", "tigris-bookmarks@localhost"],
+        )?;
+    }
+
+    let remote = settings.git_remote.trim();
+
+    if remote.is_empty() {
+        return Ok(());
+    }
+
+    match git(sync_dir, &["remote", "get-url", "origin"]) {
+        Ok(url) if url.trim() == remote => Ok(()),
+        Ok(_) => git(sync_dir, &["remote", "set-url", "origin", remote]).map(|_| ()),
+        Err(_) => git(sync_dir, &["remote", "add", "origin", remote]).map(|_| ()),
+    }
+}
+
+fn commit_db(sync_dir: &Path, db: &BookmarksDB, message: &str) -> Result<(), String> {
+    write_repo_db(sync_dir, db)?;
+    git(sync_dir, &["add", DB_FILE])?;
+
+    // Nothing to commit when the db didn't change
+    if git(sync_dir, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(());
+    }
+
+    git(sync_dir, &["commit", "-m", message]).map(|_| ())
+}
+
+fn write_repo_db(sync_dir: &Path, db: &BookmarksDB) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(db).map_err(|error| error.to_string())?;
+    fs::write(sync_dir.join(DB_FILE), json).map_err(|error| error.to_string())
+}
+
+fn read_db_at(sync_dir: &Path, revision: &str) -> Result<BookmarksDB, String> {
+    let json = git(sync_dir, &["show", &format!("{revision}:{DB_FILE}")])?;
+    serde_json::from_str::<BookmarksDB>(&json).map_err(|error| error.to_string())
+}
+
+fn is_ancestor(sync_dir: &Path, ancestor: &str, revision: &str) -> bool {
+    git(
+        sync_dir,
+        &["merge-base", "--is-ancestor", ancestor, revision],
+    )
+    .is_ok()
+}
+
+fn git(sync_dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(sync_dir)
+        .args(args)
+        .output()
+        .map_err(|error| error.to_string())?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{git, pull_and_push};
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB},
+        paths::{get_git_sync_dir, get_test_dirs},
+        settings::Settings,
+    };
+
+    fn db_with(bookmarks: Vec<Bookmark>) -> BookmarksDB {
+        BookmarksDB {
+            bookmarks,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merges_the_bookmarks_of_two_machines() {
+        let remote = get_test_dirs();
+        let remote_dir = &remote.data_dir;
+        git(remote_dir, &["init", "--bare"]).unwrap();
+
+        let settings = Settings {
+            git_sync: true,
+            git_remote: remote_dir.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        let first = get_test_dirs();
+        let second = get_test_dirs();
+        let shared = Bookmark::new("Rust", "https://rust-lang.org");
+        let first_db = db_with(vec![shared.to_owned()]);
+
+        assert_eq!(pull_and_push(&first, &settings, &first_db), Ok(None));
+
+        let second_db = db_with(vec![Bookmark::new("Go", "https://go.dev")]);
+        let merged = pull_and_push(&second, &settings, &second_db)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(merged.bookmarks.len(), 2);
+
+        // The first machine only fast forwards to the merge
+        let pulled = pull_and_push(&first, &settings, &first_db)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(pulled, merged);
+        assert_eq!(
+            git(&get_git_sync_dir(&first), &["rev-parse", "HEAD"]),
+            git(&get_git_sync_dir(&second), &["rev-parse", "HEAD"])
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+use keyring::Entry;
+use reqwest::{
+    header::{HeaderMap, ETAG, IF_MATCH, IF_NONE_MATCH, IF_UNMODIFIED_SINCE, LAST_MODIFIED},
+    Client, RequestBuilder, StatusCode,
+};
+
+use crate::{
+    bookmarks::BookmarksDB,
+    merge::merge_by_timestamp,
+    paths::Dirs,
+    settings::{get_settings, Settings},
+    sync_state::{get_sync_state, record_sync_error, record_sync_success, write_sync_state},
+};
+
+const MAX_ATTEMPTS: usize = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const KEYRING_SERVICE: &str = "tigris-bookmarks-http-sync";
+
+enum Precondition {
+    Missing,
+    ETag(String),
+    LastModified(String),
+}
+
+pub async fn sync_with_http(dirs: &Dirs, db: &BookmarksDB) -> Result<Option<BookmarksDB>, String> {
+    let settings = get_settings(dirs);
+
+    if settings.http_sync_url.trim().is_empty() {
+        return Ok(None);
+    }
+
+    match pull_and_push(dirs, &settings, db).await {
+        Ok(merged) => {
+            record_sync_success(dirs);
+            Ok(merged)
+        }
+        Err(error) => {
+            record_sync_error(dirs, &error);
+            Err(error)
+        }
+    }
+}
+
+async fn pull_and_push(
+    dirs: &Dirs,
+    settings: &Settings,
+    db: &BookmarksDB,
+) -> Result<Option<BookmarksDB>, String> {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|error| error.to_string())?;
+    let password = get_http_password(settings)?;
+    let state = get_sync_state(dirs);
+    let mut known_etag = state.etag;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let mut request = authorize(
+            client.get(settings.http_sync_url.trim()),
+            settings,
+            &password,
+        );
+
+        if let Some(etag) = &known_etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        let response = request.send().await.map_err(|error| error.to_string())?;
+
+        let (upload, precondition) = match (response.status(), &known_etag) {
+            (StatusCode::NOT_FOUND, _) => (db.to_owned(), Precondition::Missing),
+            // Nothing changed on the server since the last sync
+            (StatusCode::NOT_MODIFIED, Some(etag)) => {
+                if state.synced_hash == Some(hash_db(db)?) {
+                    return Ok(None);
+                }
+
+                (db.to_owned(), Precondition::ETag(etag.to_owned()))
+            }
+            (status, _) if status.is_success() => {
+                let headers = response.headers().to_owned();
+                let json = response.text().await.map_err(|error| error.to_string())?;
+                let remote = serde_json::from_str::<BookmarksDB>(&json)
+                    .map_err(|error| format!("The server db isn't valid: {error}"))?;
+
+                let mut merged = merge_by_timestamp(db, &remote);
+                merged.warnings = vec![];
+
+                if merged == remote {
+                    save_synced(dirs, get_header(&headers, ETAG), &merged)?;
+                    return Ok(changed(db, merged));
+                }
+
+                let precondition = match (
+                    get_header(&headers, ETAG),
+                    get_header(&headers, LAST_MODIFIED),
+                ) {
+                    (Some(etag), _) => Precondition::ETag(etag),
+                    (None, Some(last_modified)) => Precondition::LastModified(last_modified),
+                    (None, None) => {
+                        return Err(String::from(
+                            "The server sends neither an ETag nor a Last-Modified date, so an upload could overwrite the changes of another machine",
+                        ))
+                    }
+                };
+
+                (merged, precondition)
+            }
+            (status, _) => return Err(format!("The server answered {status}")),
+        };
+
+        let json = serde_json::to_string(&upload).map_err(|error| error.to_string())?;
+        let mut request = authorize(
+            client.put(settings.http_sync_url.trim()),
+            settings,
+            &password,
+        )
+        .header("Content-Type", "application/json")
+        .body(json);
+
+        request = match &precondition {
+            Precondition::Missing => request.header(IF_NONE_MATCH, "*"),
+            Precondition::ETag(etag) => request.header(IF_MATCH, etag),
+            Precondition::LastModified(last_modified) => {
+                request.header(IF_UNMODIFIED_SINCE, last_modified)
+            }
+        };
+
+        let response = request.send().await.map_err(|error| error.to_string())?;
+
+        match response.status() {
+            // Someone else uploaded after the download, so download their version and merge again
+            StatusCode::PRECONDITION_FAILED => {
+                known_etag = None;
+                continue;
+            }
+            status if status.is_success() => {
+                // Without an ETag the next sync downloads the whole db, which merges to the same result
+                save_synced(dirs, get_header(response.headers(), ETAG), &upload)?;
+                return Ok(changed(db, upload));
+            }
+            status => return Err(format!("The server answered {status} to the upload")),
+        }
+    }
+
+    Err(String::from(
+        "The db kept changing on the server while uploading, try again",
+    ))
+}
+
+fn authorize(
+    request: RequestBuilder,
+    settings: &Settings,
+    password: &Option<String>,
+) -> RequestBuilder {
+    if settings.http_sync_username.is_empty() {
+        return request;
+    }
+
+    request.basic_auth(&settings.http_sync_username, password.as_ref())
+}
+
+fn get_password_entry(settings: &Settings) -> Result<Entry, String> {
+    let account = format!(
+        "{}@{}",
+        settings.http_sync_username,
+        settings.http_sync_url.trim()
+    );
+
+    Entry::new(KEYRING_SERVICE, &account).map_err(|error| error.to_string())
+}
+
+pub fn set_http_password(settings: &Settings, password: &str) -> Result<(), String> {
+    get_password_entry(settings)?
+        .set_password(password)
+        .map_err(|error| error.to_string())
+}
+
+fn get_http_password(settings: &Settings) -> Result<Option<String>, String> {
+    if settings.http_sync_username.is_empty() {
+        return Ok(None);
+    }
+
+    match get_password_entry(settings)?.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+fn get_header(headers: &HeaderMap, name: impl reqwest::header::AsHeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_owned())
+}
+
+fn hash_db(db: &BookmarksDB) -> Result<u64, String> {
+    let json = serde_json::to_string(db).map_err(|error| error.to_string())?;
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+
+    Ok(hasher.finish())
+}
+
+fn save_synced(dirs: &Dirs, etag: Option<String>, db: &BookmarksDB) -> Result<(), String> {
+    let mut state = get_sync_state(dirs);
+    state.etag = etag;
+    state.synced_hash = Some(hash_db(db)?);
+
+    write_sync_state(dirs, &state);
+
+    Ok(())
+}
+
+fn changed(db: &BookmarksDB, merged: BookmarksDB) -> Option<BookmarksDB> {
+    let mut db = db.to_owned();
+    db.warnings = vec![];
+
+    if db == merged {
+        None
+    } else {
+        Some(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use super::pull_and_push;
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB},
+        paths::get_test_dirs,
+        settings::Settings,
+        sync_state::get_sync_state,
+    };
+
+    fn serve(responses: Vec<String>) -> (Settings, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let settings = Settings {
+            http_sync_url: format!("http://{}/db.json", listener.local_addr().unwrap()),
+            ..Default::default()
+        };
+
+        let server = thread::spawn(move || {
+            let mut requests = Vec::<String>::new();
+
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if line == "\r\n" {
+                        break;
+                    }
+
+                    request.push_str(&line.to_lowercase());
+                }
+
+                let length = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length: "))
+                    .map_or(0, |length| length.trim().parse::<usize>().unwrap());
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8_lossy(&body));
+
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+
+            requests
+        });
+
+        (settings, server)
+    }
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn db_with(bookmarks: Vec<Bookmark>) -> BookmarksDB {
+        BookmarksDB {
+            bookmarks,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn creates_the_db_when_the_server_has_none() {
+        let dirs = get_test_dirs();
+        let (settings, server) = serve(vec![
+            response("404 Not Found", "", ""),
+            response("201 Created", "ETag: \"1\"\r\n", ""),
+        ]);
+
+        let db = db_with(vec![Bookmark::new("Rust", "https://rust-lang.org")]);
+
+        assert_eq!(pull_and_push(&dirs, &settings, &db).await, Ok(None));
+
+        let requests = server.join().unwrap();
+
+        assert!(requests[1].starts_with("put"));
+        assert!(requests[1].contains("if-none-match: *"));
+        assert_eq!(get_sync_state(&dirs).etag.as_deref(), Some("\"1\""));
+    }
+
+    #[tokio::test]
+    async fn refuses_to_upload_when_the_server_has_no_etag() {
+        let dirs = get_test_dirs();
+        let remote = db_with(vec![Bookmark::new("Go", "https://go.dev")]);
+        let (settings, server) = serve(vec![response(
+            "200 OK",
+            "",
+            &serde_json::to_string(&remote).unwrap(),
+        )]);
+
+        let db = db_with(vec![Bookmark::new("Rust", "https://rust-lang.org")]);
+
+        assert!(pull_and_push(&dirs, &settings, &db).await.is_err());
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn skips_the_upload_when_nothing_changed() {
+        let dirs = get_test_dirs();
+        let db = db_with(vec![Bookmark::new("Rust", "https://rust-lang.org")]);
+        let (settings, server) = serve(vec![
+            response("404 Not Found", "", ""),
+            response("201 Created", "ETag: \"1\"\r\n", ""),
+            response("304 Not Modified", "", ""),
+        ]);
+
+        pull_and_push(&dirs, &settings, &db).await.unwrap();
+
+        assert_eq!(pull_and_push(&dirs, &settings, &db).await, Ok(None));
+
+        let requests = server.join().unwrap();
+
+        assert_eq!(requests.len(), 3);
+        assert!(requests[2].contains("if-none-match: \"1\""));
+    }
+}
@@ -0,0 +1,171 @@
+use regex::Regex;
+
+use crate::bookmarks::{new_id, now, Bookmark, BookmarksDB, Group};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedBookmark {
+    pub name: String,
+    pub link: String,
+    pub added_at: Option<u64>,
+    pub modified_at: Option<u64>,
+}
+
+pub fn parse_bookmarks_html(html: &str) -> Vec<ImportedBookmark> {
+    let anchor_regex = Regex::new(r#"(?is)<a\s+([^>]*)>(.*?)</a>"#).unwrap();
+    let attribute_regex = Regex::new(r#"(?i)([a-z_]+)\s*=\s*"([^"]*)""#).unwrap();
+    let tag_regex = Regex::new(r"<[^>]*>").unwrap();
+
+    anchor_regex
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let attributes = captures.get(1).unwrap().as_str();
+            let mut link = None;
+            let mut added_at = None;
+            let mut modified_at = None;
+
+            for attribute in attribute_regex.captures_iter(attributes) {
+                let value = attribute.get(2).unwrap().as_str();
+
+                match attribute.get(1).unwrap().as_str().to_uppercase().as_str() {
+                    "HREF" => link = Some(decode_entities(value)),
+                    "ADD_DATE" => added_at = value.parse::<u64>().ok(),
+                    "LAST_MODIFIED" => modified_at = value.parse::<u64>().ok(),
+                    _ => {}
+                }
+            }
+
+            let link = link?;
+
+            if !link.starts_with("http://") && !link.starts_with("https://") {
+                return None;
+            }
+
+            let name = decode_entities(
+                tag_regex
+                    .replace_all(captures.get(2).unwrap().as_str(), "")
+                    .trim(),
+            );
+            let name = if name.is_empty() {
+                link.to_owned()
+            } else {
+                name
+            };
+
+            Some(ImportedBookmark {
+                name,
+                link,
+                added_at,
+                modified_at,
+            })
+        })
+        .collect()
+}
+
+pub fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+pub fn import_bookmarks(db: &mut BookmarksDB, bookmarks: Vec<ImportedBookmark>) -> Vec<Bookmark> {
+    let mut imported = Vec::<Bookmark>::new();
+
+    for imported_bookmark in bookmarks {
+        let exists = db
+            .bookmarks
+            .iter()
+            .any(|bookmark| bookmark.link == imported_bookmark.link);
+
+        if exists {
+            continue;
+        }
+
+        let created_at = imported_bookmark.added_at.unwrap_or(now());
+
+        let bookmark = Bookmark {
+            created_at,
+            updated_at: imported_bookmark.modified_at.unwrap_or(created_at),
+            ..Bookmark::new(&imported_bookmark.name, &imported_bookmark.link)
+        };
+
+        db.bookmarks.push(bookmark.to_owned());
+        imported.push(bookmark);
+    }
+
+    imported
+}
+
+pub fn import_db(db: &mut BookmarksDB, other: BookmarksDB) -> Vec<Bookmark> {
+    let mut imported = Vec::<Bookmark>::new();
+    let mut ids = Vec::<(String, String)>::new();
+
+    for bookmark in other.bookmarks {
+        match db
+            .bookmarks
+            .iter()
+            .find(|existing| existing.link == bookmark.link)
+        {
+            Some(existing) => ids.push((bookmark.id, existing.id.to_owned())),
+            None => {
+                let new_bookmark = Bookmark {
+                    tags: bookmark.tags,
+                    folder: bookmark.folder,
+                    private: bookmark.private,
+                    created_at: bookmark.created_at,
+                    updated_at: bookmark.updated_at,
+                    ..Bookmark::new(&bookmark.name, &bookmark.link)
+                };
+
+                ids.push((bookmark.id, new_bookmark.id.to_owned()));
+                db.bookmarks.push(new_bookmark.to_owned());
+                imported.push(new_bookmark);
+            }
+        }
+    }
+
+    let mut group_ids = Vec::<(String, String)>::new();
+
+    for group in &other.groups {
+        match db
+            .groups
+            .iter()
+            .find(|existing| existing.name == group.name)
+        {
+            Some(existing) => group_ids.push((group.id.to_owned(), existing.id.to_owned())),
+            None => group_ids.push((group.id.to_owned(), new_id())),
+        }
+    }
+
+    let map_ids = |old_ids: &Vec<String>, new_ids: &Vec<(String, String)>| {
+        old_ids
+            .iter()
+            .filter_map(|id| new_ids.iter().find(|(old, _)| old == id))
+            .map(|(_, new)| new.to_owned())
+            .collect::<Vec<String>>()
+    };
+
+    for group in other.groups {
+        if db.groups.iter().any(|existing| existing.name == group.name) {
+            continue;
+        }
+
+        let bookmarks_ids = map_ids(&group.bookmarks_ids, &ids);
+        let groups_ids = map_ids(&group.groups_ids, &group_ids);
+        let (_, id) = group_ids.iter().find(|(old, _)| *old == group.id).unwrap();
+
+        db.groups.push(Group {
+            id: id.to_owned(),
+            groups_ids,
+            rule: group.rule,
+            private: group.private,
+            launch: group.launch,
+            created_at: group.created_at,
+            updated_at: group.updated_at,
+            ..Group::new(&group.name, &bookmarks_ids)
+        });
+    }
+
+    imported
+}
@@ -0,0 +1,271 @@
+use std::fs;
+
+use crate::{
+    bookmarks::{is_valid_link, new_id, BookmarksDB},
+    paths::{get_favicons_dir, Dirs},
+};
+
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub dangling_ids: usize,
+    pub duplicate_ids: usize,
+    pub orphan_favicons: usize,
+    pub invalid_links: Vec<String>,
+    pub empty_groups: Vec<String>,
+}
+
+pub fn check_db(dirs: &Dirs, db: &mut BookmarksDB) -> IntegrityReport {
+    IntegrityReport {
+        duplicate_ids: repair_duplicate_ids(db),
+        dangling_ids: repair_dangling_ids(db),
+        orphan_favicons: remove_orphan_favicons(dirs, db),
+        invalid_links: db
+            .bookmarks
+            .iter()
+            .filter(|bookmark| !is_valid_link(&bookmark.link))
+            .map(|bookmark| bookmark.name.to_owned())
+            .collect(),
+        empty_groups: db
+            .groups
+            .iter()
+            .filter(|group| {
+                group.bookmarks_ids.is_empty()
+                    && group.groups_ids.is_empty()
+                    && group.rule.is_none()
+            })
+            .map(|group| group.name.to_owned())
+            .collect(),
+    }
+}
+
+fn repair_duplicate_ids(db: &mut BookmarksDB) -> usize {
+    let mut seen_ids = Vec::<String>::new();
+    let mut repaired = 0;
+
+    let bookmarks = db.bookmarks.iter_mut().chain(
+        db.trash
+            .bookmarks
+            .iter_mut()
+            .map(|trashed| &mut trashed.bookmark),
+    );
+
+    for bookmark in bookmarks {
+        if seen_ids.contains(&bookmark.id) {
+            bookmark.id = new_id();
+            repaired += 1;
+        }
+
+        seen_ids.push(bookmark.id.to_owned());
+    }
+
+    seen_ids.clear();
+
+    let groups = db
+        .groups
+        .iter_mut()
+        .chain(db.trash.groups.iter_mut().map(|trashed| &mut trashed.group));
+
+    for group in groups {
+        if seen_ids.contains(&group.id) {
+            group.id = new_id();
+            repaired += 1;
+        }
+
+        seen_ids.push(group.id.to_owned());
+    }
+
+    repaired
+}
+
+fn repair_dangling_ids(db: &mut BookmarksDB) -> usize {
+    let bookmarks_ids = db
+        .bookmarks
+        .iter()
+        .map(|bookmark| bookmark.id.to_owned())
+        .collect::<Vec<String>>();
+
+    let groups_ids = db
+        .groups
+        .iter()
+        .chain(db.trash.groups.iter().map(|trashed| &trashed.group))
+        .map(|group| group.id.to_owned())
+        .collect::<Vec<String>>();
+
+    let mut repaired = 0;
+
+    for group in db.groups.iter_mut() {
+        let mut kept_ids = Vec::<String>::new();
+
+        for id in &group.bookmarks_ids {
+            if bookmarks_ids.contains(id) && !kept_ids.contains(id) {
+                kept_ids.push(id.to_owned());
+            } else {
+                repaired += 1;
+            }
+        }
+
+        group.bookmarks_ids = kept_ids;
+
+        let mut kept_groups_ids = Vec::<String>::new();
+
+        for id in &group.groups_ids {
+            if groups_ids.contains(id) && id != &group.id && !kept_groups_ids.contains(id) {
+                kept_groups_ids.push(id.to_owned());
+            } else {
+                repaired += 1;
+            }
+        }
+
+        group.groups_ids = kept_groups_ids;
+    }
+
+    repaired
+}
+
+fn remove_orphan_favicons(dirs: &Dirs, db: &BookmarksDB) -> usize {
+    let favicons_dir = get_favicons_dir(dirs);
+
+    if !favicons_dir.exists() {
+        return 0;
+    }
+
+    let bookmarks_ids = db
+        .bookmarks
+        .iter()
+        .chain(db.trash.bookmarks.iter().map(|trashed| &trashed.bookmark))
+        .map(|bookmark| bookmark.id.to_owned())
+        .collect::<Vec<String>>();
+
+    let mut removed = 0;
+
+    for entry in fs::read_dir(&favicons_dir).expect("Error reading favicons directory") {
+        let path = entry.expect("Error reading favicon").path();
+
+        let is_orphan = path
+            .file_stem()
+            .map(|stem| !bookmarks_ids.contains(&stem.to_string_lossy().to_string()))
+            .unwrap_or(false);
+
+        if is_orphan && path.is_file() {
+            fs::remove_file(&path).expect("Error deleting favicon");
+            removed += 1;
+        }
+    }
+
+    removed
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.dangling_ids == 0
+            && self.duplicate_ids == 0
+            && self.orphan_favicons == 0
+            && self.invalid_links.is_empty()
+            && self.empty_groups.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return String::from("No problems found");
+        }
+
+        let mut lines = Vec::<String>::new();
+
+        if self.dangling_ids > 0 {
+            lines.push(format!(
+                "Removed {} dangling group references",
+                self.dangling_ids
+            ));
+        }
+
+        if self.duplicate_ids > 0 {
+            lines.push(format!("Gave new ids to {} duplicates", self.duplicate_ids));
+        }
+
+        if self.orphan_favicons > 0 {
+            lines.push(format!("Deleted {} orphan favicons", self.orphan_favicons));
+        }
+
+        if !self.invalid_links.is_empty() {
+            lines.push(format!("Invalid links: {}", self.invalid_links.join(", ")));
+        }
+
+        if !self.empty_groups.is_empty() {
+            lines.push(format!("Empty groups: {}", self.empty_groups.join(", ")));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::check_db;
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB, Group},
+        paths::{get_favicon_path, get_favicons_dir, get_test_dirs},
+    };
+
+    fn test_db() -> BookmarksDB {
+        let bookmark = Bookmark::new("Rust", "https://rust-lang.org");
+
+        BookmarksDB {
+            groups: vec![Group::new("Work", &vec![bookmark.id.to_owned()])],
+            bookmarks: vec![bookmark],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_nothing_for_a_clean_db() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let mut checked = db.to_owned();
+        let report = check_db(&dirs, &mut checked);
+
+        assert!(report.is_clean());
+        assert_eq!(report.summary(), "No problems found");
+        assert_eq!(checked, db);
+    }
+
+    #[test]
+    fn removes_dangling_group_references() {
+        let dirs = get_test_dirs();
+        let mut db = test_db();
+        let bookmark_id = db.bookmarks[0].id.to_owned();
+        let group_id = db.groups[0].id.to_owned();
+
+        db.groups[0].bookmarks_ids = vec![
+            bookmark_id.to_owned(),
+            "missing".to_owned(),
+            bookmark_id.to_owned(),
+        ];
+        db.groups[0].groups_ids = vec![group_id, "missing".to_owned()];
+
+        let report = check_db(&dirs, &mut db);
+
+        assert_eq!(report.dangling_ids, 4);
+        assert_eq!(db.groups[0].bookmarks_ids, vec![bookmark_id]);
+        assert!(db.groups[0].groups_ids.is_empty());
+    }
+
+    #[test]
+    fn deletes_orphan_favicons() {
+        let dirs = get_test_dirs();
+        let mut db = test_db();
+        let kept = get_favicon_path(&dirs, &db.bookmarks[0].id);
+        let orphan = get_favicon_path(&dirs, "missing");
+
+        fs::create_dir_all(get_favicons_dir(&dirs)).unwrap();
+        fs::write(&kept, "").unwrap();
+        fs::write(&orphan, "").unwrap();
+
+        let report = check_db(&dirs, &mut db);
+
+        assert_eq!(report.orphan_favicons, 1);
+        assert!(kept.exists());
+        assert!(!orphan.exists());
+    }
+}
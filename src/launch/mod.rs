@@ -0,0 +1,74 @@
+use std::{thread, time::Duration};
+
+use crate::bookmarks::{BookmarksDB, LaunchOptions};
+
+pub fn open_group(
+    db: &mut BookmarksDB,
+    group_id: &str,
+    selected: Option<&Vec<String>>,
+) -> Result<(), String> {
+    let group = match db.groups.iter().find(|group| group.id == group_id) {
+        Some(group) => group,
+        None => return Err(String::from("The group doesn't exist anymore")),
+    };
+
+    let links = group
+        .get_launch_bookmarks(&db.bookmarks, &db.groups)
+        .into_iter()
+        .filter(|bookmark| selected.is_none_or(|selected| selected.contains(&bookmark.id)))
+        .map(|bookmark| bookmark.link.to_owned())
+        .collect::<Vec<String>>();
+
+    open_links(&links, &group.launch)?;
+
+    db.groups
+        .iter_mut()
+        .filter(|group| group.id == group_id)
+        .for_each(|group| group.mark_opened());
+
+    Ok(())
+}
+
+pub fn open_links(links: &[String], options: &LaunchOptions) -> Result<(), String> {
+    if options.delay_ms == 0 {
+        let handles = links
+            .iter()
+            .map(|link| {
+                let link = link.to_owned();
+                let options = options.to_owned();
+
+                thread::spawn(move || open_link(&link, &options))
+            })
+            .collect::<Vec<_>>();
+
+        // Every link gets opened before the first error is reported
+        let results = handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(String::from("Error opening the links")))
+            })
+            .collect::<Vec<_>>();
+
+        return results.into_iter().collect();
+    }
+
+    for (index, link) in links.iter().enumerate() {
+        if index > 0 {
+            thread::sleep(Duration::from_millis(options.delay_ms));
+        }
+
+        open_link(link, options)?;
+    }
+
+    Ok(())
+}
+
+fn open_link(link: &str, options: &LaunchOptions) -> Result<(), String> {
+    match &options.browser {
+        Some(browser) => open::with(link, browser),
+        None => open::that(link),
+    }
+    .map_err(|error| format!("{link} couldn't be opened: {error}"))
+}
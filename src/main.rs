@@ -1,32 +1,69 @@
 use actions::handle_actions;
+use cli::{is_cli_invocation, run_cli};
 use forms::handle_forms;
+use paths::Dirs;
 use results::handle_results;
+use storage::open_store;
 use tigris_core::features::api::{
     get_request,
     RequestType::{FormResults, GetResults, RunAction},
 };
+use tigris_core::features::utils::send_notification;
 
 pub mod actions;
+pub mod archives;
 pub mod bookmarks;
+pub mod cli;
+pub mod clipboard;
+pub mod collections;
+pub mod declarative;
+pub mod encryption;
+pub mod export;
+pub mod favicons;
 pub mod forms;
+pub mod git_sync;
+pub mod http_sync;
 pub mod icons;
+pub mod import;
+pub mod integrity;
+pub mod launch;
+pub mod merge;
+pub mod pages;
 pub mod paths;
+pub mod profiles;
 pub mod results;
+pub mod rules;
+pub mod settings;
+pub mod storage;
+pub mod store;
+pub mod sync_state;
 
 #[tokio::main]
 async fn main() {
+    if is_cli_invocation() {
+        run_cli().await;
+        return;
+    }
+
     let request = get_request().unwrap();
+    let dirs = Dirs::active();
 
     match request.request_type {
         GetResults => {
-            handle_results(request.get_results_request.unwrap());
-        }
-        RunAction => {
-            handle_actions(request.run_action_request.unwrap());
+            handle_results(request.get_results_request.unwrap(), &dirs);
         }
+        RunAction => match open_store(&dirs) {
+            Ok(store) => handle_actions(request.run_action_request.unwrap(), store.as_ref()).await,
+            Err(error) => send_notification("Error Opening Bookmarks", &error),
+        },
         FormResults => {
-            tokio::spawn(async {
-                handle_forms(request.form_results_request.unwrap()).await;
+            tokio::spawn(async move {
+                match open_store(&dirs) {
+                    Ok(store) => {
+                        handle_forms(request.form_results_request.unwrap(), store.as_ref()).await
+                    }
+                    Err(error) => send_notification("Error Opening Bookmarks", &error),
+                }
             })
             .await
             .unwrap();
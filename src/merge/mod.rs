@@ -0,0 +1,474 @@
+use serde_json::{Map, Value};
+
+use crate::bookmarks::{
+    Bookmark, BookmarksDB, Group, ReadLaterItem, Tombstone, TrashedBookmark, TrashedGroup,
+};
+
+pub fn merge_dbs(base: &BookmarksDB, ours: &BookmarksDB, theirs: &BookmarksDB) -> BookmarksDB {
+    let bookmarks = merge_records::<Bookmark>(
+        &base.bookmarks,
+        &ours.bookmarks,
+        &theirs.bookmarks,
+        |bookmark| bookmark.id.to_owned(),
+    );
+
+    let groups = merge_records::<Group>(&base.groups, &ours.groups, &theirs.groups, |group| {
+        group.id.to_owned()
+    });
+
+    let mut trashed_bookmarks = ours.trash.bookmarks.to_owned();
+
+    for trashed in &theirs.trash.bookmarks {
+        if !trashed_bookmarks
+            .iter()
+            .any(|existing| existing.bookmark.id == trashed.bookmark.id)
+        {
+            trashed_bookmarks.push(trashed.to_owned());
+        }
+    }
+
+    let mut trashed_groups = ours.trash.groups.to_owned();
+
+    for trashed in &theirs.trash.groups {
+        if !trashed_groups
+            .iter()
+            .any(|existing| existing.group.id == trashed.group.id)
+        {
+            trashed_groups.push(trashed.to_owned());
+        }
+    }
+
+    // Something edited on one machine and deleted on the other stays alive, so it leaves the trash
+    trashed_bookmarks.retain(|trashed: &TrashedBookmark| {
+        !bookmarks
+            .iter()
+            .any(|bookmark| bookmark.id == trashed.bookmark.id)
+    });
+
+    trashed_groups
+        .retain(|trashed: &TrashedGroup| !groups.iter().any(|group| group.id == trashed.group.id));
+
+    let mut merged = ours.to_owned();
+    merged.version = ours.version.max(theirs.version);
+    merged.bookmarks = bookmarks;
+    merged.groups = groups;
+    merged.trash.bookmarks = trashed_bookmarks;
+    merged.trash.groups = trashed_groups;
+    merged.tombstones = merge_tombstones(&ours.tombstones, &theirs.tombstones);
+    merged.read_later = merge_records::<ReadLaterItem>(
+        &base.read_later,
+        &ours.read_later,
+        &theirs.read_later,
+        |item| item.id.to_owned(),
+    );
+
+    merged
+}
+
+pub fn merge_by_timestamp(local: &BookmarksDB, remote: &BookmarksDB) -> BookmarksDB {
+    let tombstones = merge_tombstones(&local.tombstones, &remote.tombstones);
+
+    let mut deletions = tombstones.to_owned();
+
+    for trashed in local.trash.bookmarks.iter().chain(&remote.trash.bookmarks) {
+        deletions.push(Tombstone {
+            id: trashed.bookmark.id.to_owned(),
+            deleted_at: trashed.deleted_at,
+        });
+    }
+
+    for trashed in local.trash.groups.iter().chain(&remote.trash.groups) {
+        deletions.push(Tombstone {
+            id: trashed.group.id.to_owned(),
+            deleted_at: trashed.deleted_at,
+        });
+    }
+
+    let deleted_at = |id: &str| {
+        deletions
+            .iter()
+            .filter(|deletion| deletion.id == id)
+            .map(|deletion| deletion.deleted_at)
+            .max()
+    };
+
+    let mut bookmarks = newest_records(&local.bookmarks, &remote.bookmarks, |bookmark| {
+        (bookmark.id.to_owned(), bookmark.updated_at)
+    })
+    .into_iter()
+    .filter(|bookmark| {
+        deleted_at(&bookmark.id).is_none_or(|deleted_at| bookmark.updated_at > deleted_at)
+    })
+    .collect::<Vec<Bookmark>>();
+
+    let mut groups = newest_records(&local.groups, &remote.groups, |group| {
+        (group.id.to_owned(), group.updated_at)
+    })
+    .into_iter()
+    .filter(|group| deleted_at(&group.id).is_none_or(|deleted_at| group.updated_at > deleted_at))
+    .collect::<Vec<Group>>();
+
+    // Without a base the opens on each side can't be told apart, so the highest count is kept
+    for bookmark in bookmarks.iter_mut() {
+        for other in local.bookmarks.iter().chain(&remote.bookmarks) {
+            if other.id == bookmark.id {
+                bookmark.last_opened_at = bookmark.last_opened_at.max(other.last_opened_at);
+                bookmark.open_count = bookmark.open_count.max(other.open_count);
+            }
+        }
+    }
+
+    for group in groups.iter_mut() {
+        for other in local.groups.iter().chain(&remote.groups) {
+            if other.id == group.id {
+                group.last_opened_at = group.last_opened_at.max(other.last_opened_at);
+                group.open_count = group.open_count.max(other.open_count);
+            }
+        }
+    }
+
+    let mut merged = local.to_owned();
+    merged.version = local.version.max(remote.version);
+
+    merged.trash.bookmarks =
+        newest_records(&local.trash.bookmarks, &remote.trash.bookmarks, |trashed| {
+            (trashed.bookmark.id.to_owned(), trashed.deleted_at)
+        })
+        .into_iter()
+        .filter(|trashed| {
+            !bookmarks
+                .iter()
+                .any(|bookmark| bookmark.id == trashed.bookmark.id)
+                && !tombstones
+                    .iter()
+                    .any(|tombstone| tombstone.id == trashed.bookmark.id)
+        })
+        .collect();
+
+    merged.trash.groups = newest_records(&local.trash.groups, &remote.trash.groups, |trashed| {
+        (trashed.group.id.to_owned(), trashed.deleted_at)
+    })
+    .into_iter()
+    .filter(|trashed| {
+        !groups.iter().any(|group| group.id == trashed.group.id)
+            && !tombstones
+                .iter()
+                .any(|tombstone| tombstone.id == trashed.group.id)
+    })
+    .collect();
+
+    merged.bookmarks = bookmarks;
+    merged.groups = groups;
+    merged.tombstones = tombstones;
+    merged.read_later = newest_records(&local.read_later, &remote.read_later, |item| {
+        (item.id.to_owned(), item.updated_at)
+    });
+
+    merged
+}
+
+fn newest_records<T: Clone>(
+    local: &Vec<T>,
+    remote: &Vec<T>,
+    get_key: impl Fn(&T) -> (String, u64),
+) -> Vec<T> {
+    let mut records = local.to_owned();
+
+    for remote_record in remote {
+        let (id, timestamp) = get_key(remote_record);
+
+        match records.iter().position(|record| get_key(record).0 == id) {
+            Some(position) => {
+                if timestamp > get_key(&records[position]).1 {
+                    records[position] = remote_record.to_owned();
+                }
+            }
+            None => records.push(remote_record.to_owned()),
+        }
+    }
+
+    records
+}
+
+fn merge_tombstones(ours: &Vec<Tombstone>, theirs: &Vec<Tombstone>) -> Vec<Tombstone> {
+    newest_records(ours, theirs, |tombstone| {
+        (tombstone.id.to_owned(), tombstone.deleted_at)
+    })
+}
+
+pub fn merge_records<T>(
+    base: &Vec<T>,
+    ours: &Vec<T>,
+    theirs: &Vec<T>,
+    get_id: impl Fn(&T) -> String,
+) -> Vec<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + Clone,
+{
+    let mut ids = Vec::<String>::new();
+
+    for record in ours.iter().chain(theirs.iter()).chain(base.iter()) {
+        let id = get_id(record);
+
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    let find = |records: &Vec<T>, id: &str| {
+        records
+            .iter()
+            .find(|record| get_id(record) == id)
+            .map(|record| serde_json::to_value(record).expect("Error serializing record"))
+    };
+
+    ids.iter()
+        .filter_map(|id| {
+            let merged = merge_record(
+                find(base, id).as_ref(),
+                find(ours, id).as_ref(),
+                find(theirs, id).as_ref(),
+            )?;
+
+            serde_json::from_value::<T>(merged).ok()
+        })
+        .collect()
+}
+
+fn merge_record(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+) -> Option<Value> {
+    match (base, ours, theirs) {
+        (_, None, None) => None,
+        (None, Some(ours), None) => Some(ours.to_owned()),
+        (None, None, Some(theirs)) => Some(theirs.to_owned()),
+        (Some(base), Some(ours), None) => {
+            if ours == base {
+                None
+            } else {
+                Some(ours.to_owned())
+            }
+        }
+        (Some(base), None, Some(theirs)) => {
+            if theirs == base {
+                None
+            } else {
+                Some(theirs.to_owned())
+            }
+        }
+        (base, Some(ours), Some(theirs)) => Some(merge_fields(base, ours, theirs)),
+    }
+}
+
+fn merge_fields(base: Option<&Value>, ours: &Value, theirs: &Value) -> Value {
+    let empty = Map::new();
+    let base_fields = base.and_then(|base| base.as_object()).unwrap_or(&empty);
+    let our_fields = ours.as_object().unwrap_or(&empty);
+    let their_fields = theirs.as_object().unwrap_or(&empty);
+
+    let ours_is_newer = updated_at(ours) >= updated_at(theirs);
+    let mut merged = Map::new();
+
+    for key in our_fields.keys().chain(their_fields.keys()) {
+        if merged.contains_key(key) {
+            continue;
+        }
+
+        let base_value = base_fields.get(key);
+        let our_value = our_fields.get(key);
+        let their_value = their_fields.get(key);
+
+        // Opening isn't an edit, so the opens of both sides are kept whichever side is newer
+        if let Some(value) = merge_opens(key, base_value, our_value, their_value) {
+            merged.insert(key.to_owned(), value);
+            continue;
+        }
+
+        let value = if our_value == their_value || their_value == base_value {
+            our_value
+        } else if our_value == base_value {
+            their_value
+        } else if let (Some(Value::Array(our_items)), Some(Value::Array(their_items))) =
+            (our_value, their_value)
+        {
+            let base_items = match base_value {
+                Some(Value::Array(base_items)) => base_items.to_owned(),
+                _ => vec![],
+            };
+
+            merged.insert(
+                key.to_owned(),
+                Value::Array(merge_sets(&base_items, our_items, their_items)),
+            );
+
+            continue;
+        } else if ours_is_newer {
+            our_value
+        } else {
+            their_value
+        };
+
+        if let Some(value) = value {
+            merged.insert(key.to_owned(), value.to_owned());
+        }
+    }
+
+    Value::Object(merged)
+}
+
+fn merge_opens(
+    key: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+) -> Option<Value> {
+    let as_u64 = |value: Option<&Value>| value.and_then(|value| value.as_u64());
+
+    match key {
+        "last_opened_at" => as_u64(ours).max(as_u64(theirs)).map(Value::from),
+        "open_count" => {
+            let base = as_u64(base).unwrap_or(0);
+            let ours = as_u64(ours).unwrap_or(0);
+            let theirs = as_u64(theirs).unwrap_or(0);
+
+            Some(Value::from(
+                base + ours.saturating_sub(base) + theirs.saturating_sub(base),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn merge_sets(base: &[Value], ours: &[Value], theirs: &[Value]) -> Vec<Value> {
+    let mut merged = Vec::<Value>::new();
+
+    for item in ours.iter().chain(theirs.iter()) {
+        let removed = base.contains(item) && (!ours.contains(item) || !theirs.contains(item));
+
+        if !removed && !merged.contains(item) {
+            merged.push(item.to_owned());
+        }
+    }
+
+    merged
+}
+
+fn updated_at(record: &Value) -> u64 {
+    record
+        .get("updated_at")
+        .and_then(|updated_at| updated_at.as_u64())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_by_timestamp, merge_dbs};
+    use crate::bookmarks::{Bookmark, BookmarksDB, Tombstone};
+
+    fn db_with(bookmarks: Vec<Bookmark>) -> BookmarksDB {
+        BookmarksDB {
+            bookmarks,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keeps_edits_to_different_fields() {
+        let bookmark = Bookmark::new("Rust", "https://rust-lang.org");
+        let base = db_with(vec![bookmark.to_owned()]);
+
+        let mut renamed = bookmark.to_owned();
+        renamed.name = String::from("Rust Lang");
+        renamed.updated_at += 1;
+
+        let mut tagged = bookmark.to_owned();
+        tagged.tags = vec![String::from("dev")];
+        tagged.updated_at += 2;
+
+        let merged = merge_dbs(&base, &db_with(vec![renamed]), &db_with(vec![tagged]));
+
+        assert_eq!(merged.bookmarks.len(), 1);
+        assert_eq!(merged.bookmarks[0].name, "Rust Lang");
+        assert_eq!(merged.bookmarks[0].tags, vec![String::from("dev")]);
+    }
+
+    #[test]
+    fn newest_edit_wins_the_same_field() {
+        let bookmark = Bookmark::new("Rust", "https://rust-lang.org");
+        let base = db_with(vec![bookmark.to_owned()]);
+
+        let mut ours = bookmark.to_owned();
+        ours.name = String::from("Ours");
+        ours.updated_at += 1;
+
+        let mut theirs = bookmark.to_owned();
+        theirs.name = String::from("Theirs");
+        theirs.updated_at += 2;
+
+        let merged = merge_dbs(&base, &db_with(vec![ours]), &db_with(vec![theirs]));
+
+        assert_eq!(merged.bookmarks[0].name, "Theirs");
+    }
+
+    #[test]
+    fn keeps_additions_and_deletions_from_both_sides() {
+        let kept = Bookmark::new("Rust", "https://rust-lang.org");
+        let deleted = Bookmark::new("Python", "https://python.org");
+        let added = Bookmark::new("Go", "https://go.dev");
+        let base = db_with(vec![kept.to_owned(), deleted.to_owned()]);
+
+        let ours = db_with(vec![kept.to_owned()]);
+        let theirs = db_with(vec![kept.to_owned(), deleted, added.to_owned()]);
+
+        let merged = merge_dbs(&base, &ours, &theirs);
+
+        assert_eq!(merged.bookmarks, vec![kept, added]);
+    }
+
+    #[test]
+    fn newer_tombstone_deletes_the_bookmark() {
+        let bookmark = Bookmark::new("Rust", "https://rust-lang.org");
+        let mut remote = db_with(vec![]);
+        remote.tombstones = vec![Tombstone {
+            id: bookmark.id.to_owned(),
+            deleted_at: bookmark.updated_at + 1,
+        }];
+
+        let merged = merge_by_timestamp(&db_with(vec![bookmark]), &remote);
+
+        assert!(merged.bookmarks.is_empty());
+        assert_eq!(merged.tombstones, remote.tombstones);
+    }
+
+    #[test]
+    fn opening_keeps_the_edits_of_the_other_side() {
+        let mut bookmark = Bookmark::new("Rust", "https://rust-lang.org");
+        bookmark.open_count = 2;
+        let base = db_with(vec![bookmark.to_owned()]);
+
+        let mut opened = bookmark.to_owned();
+        opened.mark_opened();
+
+        let mut renamed = bookmark.to_owned();
+        renamed.name = String::from("Rust Lang");
+        renamed.updated_at += 1;
+        renamed.mark_opened();
+
+        let merged = merge_dbs(
+            &base,
+            &db_with(vec![opened.to_owned()]),
+            &db_with(vec![renamed.to_owned()]),
+        );
+
+        assert_eq!(merged.bookmarks[0].name, "Rust Lang");
+        assert_eq!(merged.bookmarks[0].open_count, 4);
+        assert!(merged.bookmarks[0].last_opened_at.is_some());
+
+        let merged = merge_by_timestamp(&db_with(vec![opened]), &db_with(vec![renamed]));
+
+        assert_eq!(merged.bookmarks[0].name, "Rust Lang");
+        assert_eq!(merged.bookmarks[0].open_count, 3);
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::Client;
+
+use crate::import::decode_entities;
+
+const WORDS_PER_MINUTE: usize = 230;
+
+pub struct PageInfo {
+    pub title: Option<String>,
+    pub reading_minutes: u32,
+}
+
+pub async fn fetch_page(link: &str) -> Result<PageInfo, String> {
+    let response = Client::new()
+        .get(link)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|error| error.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!("The page answered {}", response.status()));
+    }
+
+    let html = response.text().await.map_err(|error| error.to_string())?;
+
+    Ok(parse_page(&html))
+}
+
+pub fn parse_page(html: &str) -> PageInfo {
+    let title_regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    let hidden_regex =
+        Regex::new(r"(?is)<(script|style|noscript|head|nav|footer)[^>]*>.*?</(script|style|noscript|head|nav|footer)>")
+            .unwrap();
+    let tag_regex = Regex::new(r"<[^>]*>").unwrap();
+
+    let title = title_regex
+        .captures(html)
+        .map(|captures| decode_entities(captures.get(1).unwrap().as_str().trim()))
+        .filter(|title| !title.is_empty());
+
+    let text = tag_regex
+        .replace_all(&hidden_regex.replace_all(html, " "), " ")
+        .to_string();
+    let words = decode_entities(&text).split_whitespace().count();
+
+    PageInfo {
+        title,
+        reading_minutes: words.div_ceil(WORDS_PER_MINUTE).max(1) as u32,
+    }
+}
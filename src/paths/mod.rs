@@ -1,17 +1,194 @@
-use std::path::PathBuf;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
-pub fn get_config_dir() -> PathBuf {
-    PathBuf::from(dirs::config_dir().unwrap()).join("tigris-bookmarks")
+use serde_json::Value;
+use tigris_core::features::extensions::get_extension_dir;
+
+use crate::profiles::{get_active_profile, DEFAULT_PROFILE};
+
+pub const DATA_DIR_VAR: &str = "TIGRIS_BOOKMARKS_DIR";
+
+pub fn get_data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os(DATA_DIR_VAR).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+
+    if let Some(dir) = get_manifest_data_dir() {
+        return dir;
+    }
+
+    dirs::config_dir().unwrap().join("tigris-bookmarks")
+}
+
+fn get_manifest_data_dir() -> Option<PathBuf> {
+    let manifest_path = get_extension_dir("bookmarks")?.join("manifest.json");
+    let manifest = serde_json::from_str::<Value>(&fs::read_to_string(manifest_path).ok()?).ok()?;
+
+    let setting = manifest
+        .get("settings")?
+        .as_array()?
+        .iter()
+        .find(|setting| setting.get("id").and_then(Value::as_str) == Some("data_dir"))?;
+
+    let dir = setting
+        .get("value")
+        .or(setting.get("default_value"))?
+        .as_str()?
+        .trim();
+
+    if dir.is_empty() {
+        return None;
+    }
+
+    match dir.strip_prefix("~/") {
+        Some(relative_dir) => Some(dirs::home_dir()?.join(relative_dir)),
+        None => Some(PathBuf::from(dir)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dirs {
+    pub data_dir: PathBuf,
+    pub profile: String,
+}
+
+impl Dirs {
+    pub fn active() -> Self {
+        let data_dir = get_data_dir();
+
+        Self {
+            profile: get_active_profile(&data_dir),
+            data_dir,
+        }
+    }
+
+    pub fn new(data_dir: &Path, profile: &str) -> Self {
+        Self {
+            data_dir: data_dir.to_owned(),
+            profile: profile.to_owned(),
+        }
+    }
+
+    pub fn with_profile(&self, profile: &str) -> Self {
+        Self::new(&self.data_dir, profile)
+    }
+
+    pub fn profile_dir(&self) -> PathBuf {
+        get_profile_dir(&self.data_dir, &self.profile)
+    }
+}
+
+pub fn get_profiles_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("profiles")
+}
+
+pub fn get_active_profile_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("active-profile")
+}
+
+pub fn get_profile_dir(data_dir: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        data_dir.to_owned()
+    } else {
+        get_profiles_dir(data_dir).join(profile)
+    }
+}
+
+pub fn get_db_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("db.json")
+}
+
+pub fn get_sqlite_db_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("db.sqlite")
+}
+
+pub fn get_encrypted_db_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("db.enc")
+}
+
+pub fn get_favicons_dir(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("favicons")
+}
+
+pub fn get_favicon_path(dirs: &Dirs, name: &str) -> PathBuf {
+    get_favicons_dir(dirs).join(format!("{name}.png"))
+}
+
+pub fn get_archives_dir(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("archives")
+}
+
+pub fn get_archive_path(dirs: &Dirs, bookmark_id: &str) -> PathBuf {
+    get_archives_dir(dirs).join(format!("{bookmark_id}.html"))
+}
+
+pub fn get_settings_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("settings.json")
+}
+
+pub fn get_declared_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("bookmarks.toml")
+}
+
+pub fn get_collections_dir(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("collections")
 }
 
-pub fn get_db_path() -> PathBuf {
-    get_config_dir().join("db.json")
+pub fn get_collection_lock_path(dirs: &Dirs) -> PathBuf {
+    get_collections_dir(dirs).join("refresh.lock")
 }
 
-pub fn get_favicons_dir() -> PathBuf {
-    get_config_dir().join("favicons")
+pub fn get_collection_cache_path(dirs: &Dirs, name: &str) -> PathBuf {
+    let file_name = name
+        .chars()
+        .map(|char| if char.is_alphanumeric() { char } else { '-' })
+        .collect::<String>();
+
+    get_collections_dir(dirs).join(format!("{file_name}.json"))
+}
+
+pub fn get_git_sync_dir(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("sync")
+}
+
+pub fn get_sync_state_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("sync-state.json")
+}
+
+pub fn get_sync_lock_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("sync.lock")
 }
 
-pub fn get_favicon_path(name: &str) -> PathBuf {
-    get_favicons_dir().join(format!("{name}.png"))
+pub fn get_sync_pending_path(dirs: &Dirs) -> PathBuf {
+    dirs.profile_dir().join("sync.pending")
+}
+
+#[cfg(test)]
+pub struct TestDirs {
+    dirs: Dirs,
+    _temp_dir: tempfile::TempDir,
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TestDirs {
+    type Target = Dirs;
+
+    fn deref(&self) -> &Dirs {
+        &self.dirs
+    }
+}
+
+#[cfg(test)]
+pub fn get_test_dirs() -> TestDirs {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("tigris-bookmarks-")
+        .tempdir()
+        .expect("Error creating test directory");
+
+    TestDirs {
+        dirs: Dirs::new(temp_dir.path(), DEFAULT_PROFILE),
+        _temp_dir: temp_dir,
+    }
 }
@@ -0,0 +1,75 @@
+use std::{env, fs, path::Path};
+
+use crate::paths::{get_active_profile_path, get_profile_dir, get_profiles_dir};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub const PROFILE_VAR: &str = "TIGRIS_BOOKMARKS_PROFILE";
+
+pub fn get_active_profile(data_dir: &Path) -> String {
+    let profile = match env::var(PROFILE_VAR) {
+        Ok(profile) if !profile.trim().is_empty() => profile.trim().to_owned(),
+        _ => fs::read_to_string(get_active_profile_path(data_dir))
+            .map(|profile| profile.trim().to_owned())
+            .unwrap_or_default(),
+    };
+
+    if profile.is_empty() || !is_profile(data_dir, &profile) {
+        return String::from(DEFAULT_PROFILE);
+    }
+
+    profile
+}
+
+pub fn get_profiles(data_dir: &Path) -> Vec<String> {
+    let mut profiles = fs::read_dir(get_profiles_dir(data_dir))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|profile| is_valid_profile_name(profile) && profile != DEFAULT_PROFILE)
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+    profiles.sort_by_key(|profile| profile.to_lowercase());
+    profiles.insert(0, String::from(DEFAULT_PROFILE));
+
+    profiles
+}
+
+pub fn is_profile(data_dir: &Path, profile: &str) -> bool {
+    profile == DEFAULT_PROFILE
+        || (is_valid_profile_name(profile) && get_profile_dir(data_dir, profile).is_dir())
+}
+
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '-' || char == '_')
+}
+
+pub fn create_profile(data_dir: &Path, name: &str) -> Result<(), String> {
+    if !is_valid_profile_name(name) {
+        return Err(String::from(
+            "A profile name can only have letters, numbers, - and _",
+        ));
+    }
+
+    if is_profile(data_dir, name) {
+        return Err(format!("There's already a profile called {name}"));
+    }
+
+    fs::create_dir_all(get_profile_dir(data_dir, name)).map_err(|error| error.to_string())
+}
+
+pub fn switch_profile(data_dir: &Path, name: &str) -> Result<(), String> {
+    if !is_profile(data_dir, name) {
+        return Err(format!("No profile called {name}"));
+    }
+
+    fs::create_dir_all(data_dir).map_err(|error| error.to_string())?;
+    fs::write(get_active_profile_path(data_dir), name).map_err(|error| error.to_string())
+}
@@ -1,5 +1,3 @@
-use std::process::exit;
-
 use sniffer_rs::sniffer::Sniffer;
 use tigris_core::features::{
     actions::{
@@ -11,41 +9,163 @@ use tigris_core::features::{
     search_results::SearchResult,
 };
 
-use crate::{bookmarks::get_db, icons::get_icon_path, paths::get_favicon_path};
+use crate::{
+    bookmarks::{
+        parse_link, Bookmark, BookmarksDB, Group, LaunchOptions, ReadLaterItem, ReadState, SortMode,
+    },
+    clipboard::{get_clipboard_link, LinkFormat},
+    collections::{get_collection_cache, is_stale, spawn_refresh},
+    icons::get_icon_path,
+    paths::{get_favicon_path, Dirs},
+    profiles::get_profiles,
+    settings::{get_settings, read_settings},
+    storage::{open_store, peek_db, StorageBackend},
+    store::BookmarkStore,
+    sync_state::{describe_elapsed, describe_sync_state, get_sync_state},
+};
+
+pub fn handle_results(request: GetResultsRequest, dirs: &Dirs) {
+    return_search_results(&load_results(request, dirs));
+}
+
+fn load_results(request: GetResultsRequest, dirs: &Dirs) -> Vec<SearchResult> {
+    if let Err(error) = read_settings(dirs) {
+        return vec![get_error_result("The settings couldn't be read", &error)];
+    }
 
-pub fn handle_results(request: GetResultsRequest) {
+    match open_store(dirs) {
+        Ok(store) => get_results(request, store.as_ref()),
+        Err(error) => vec![get_error_result("The bookmarks couldn't be opened", &error)],
+    }
+}
+
+fn get_error_result(title: &str, error: &str) -> SearchResult {
+    SearchResult::new(title)
+        .set_description(error)
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("settings"))
+}
+
+pub fn get_results(request: GetResultsRequest, store: &dyn BookmarkStore) -> Vec<SearchResult> {
+    let dirs = store.dirs();
     let input_text = request.search_text;
     let search_query = SearchQuery::from(&input_text);
     let keyword = search_query.keyword;
     let search_text = search_query.search_text;
     let mut results = Vec::<SearchResult>::new();
     let sniffer = Sniffer::new();
-    let db = get_db();
 
-    if search_text.is_empty() {
+    // Typing a search only needs the bookmarks and groups
+    let loaded_db = match keyword.is_none() && !search_text.is_empty() {
+        true => store.get_listing(),
+        false => store.load_db(),
+    };
+
+    let mut db = match loaded_db {
+        Ok(db) => db,
+        Err(error) => return vec![get_error_result("The bookmarks couldn't be read", &error)],
+    };
+
+    for warning in &db.warnings {
+        let warning_result = SearchResult::new(warning)
+            .set_description("Open bookmarks.toml to fix it")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("pencil"))
+            .set_action(&ResultAction::new_run_extension_action(
+                &RunExtensionAction::new("bookmarks", "open-declared-file"),
+            ));
+
+        results.push(warning_result);
+    }
+    let is_private_keyword = keyword.as_ref().is_some_and(|keyword| keyword == "private");
+
+    // Private items only ever show up under their keyword
+    if !is_private_keyword {
+        db.bookmarks.retain(|bookmark| !bookmark.private);
+        db.groups.retain(|group| !group.private);
+        db.trash
+            .bookmarks
+            .retain(|trashed| !trashed.bookmark.private);
+        db.trash.groups.retain(|trashed| !trashed.group.private);
+    }
+
+    let sort_mode = keyword
+        .as_ref()
+        .and_then(|keyword| SortMode::from_keyword(keyword));
+
+    let is_listing_keyword = keyword.as_ref().is_some_and(|keyword| {
+        [
+            "t", "trash", "b", "bulk", "p", "profile", "all", "private", "rl", "a", "archive",
+            "add", "g", "group",
+        ]
+        .contains(&keyword.as_str())
+    });
+
+    let order = sort_mode.unwrap_or(SortMode::Alphabetical);
+    order.sort_bookmarks(&mut db.bookmarks);
+    order.sort_groups(&mut db.groups);
+
+    if search_text.is_empty() && sort_mode.is_none() && !is_listing_keyword {
+        // Saving the page you're on only takes copying its link and picking this result
+        if let Some(link) = get_clipboard_link()
+            .filter(|link| !db.bookmarks.iter().any(|bookmark| &bookmark.link == link))
+        {
+            // The title is fetched when the form is saved, so the results never wait for the page
+            let clipboard_result = SearchResult::new("Add from Clipboard")
+                .set_description(&link)
+                .set_icon_color("accent")
+                .set_icon_path(&get_icon_path("plus"))
+                .set_action(&ResultAction::new_open_form_action(&get_add_bookmark_form(
+                    "", &link,
+                )));
+
+            results.push(clipboard_result);
+        }
+
         let add_bookmark_result = SearchResult::new("Add Bookmark")
             .set_description("Add a new bookmark")
             .set_icon_color("accent")
             .set_icon_path(&get_icon_path("plus"))
+            .set_action(&ResultAction::new_open_form_action(&get_add_bookmark_form(
+                "", "",
+            )));
+
+        let import_bookmarks_result = SearchResult::new("Import Bookmarks")
+            .set_description("Import bookmarks exported from a browser")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("plus"))
             .set_action(&ResultAction::new_open_form_action(
-                &OpenFormAction::new("bookmarks", "add-bookmark", "Add Bookmark", "Add Bookmark")
-                    .add_field(&Field::new_text_field(
-                        "name",
-                        "Name",
-                        "The bookmark name",
-                        &TextField::new("")
-                            .set_validation(&FieldValidation::new().set_not_empty(true)),
-                    ))
-                    .add_field(&Field::new_text_field(
-                        "link",
-                        "Link",
-                        "The bookmark link",
-                        &TextField::new("")
-                            .set_validation(&FieldValidation::new().set_not_empty(true)),
-                    )),
+                &OpenFormAction::new(
+                    "bookmarks",
+                    "import-bookmarks",
+                    "Import Bookmarks",
+                    "Import",
+                )
+                .add_field(&Field::new_text_field(
+                    "path",
+                    "File",
+                    "The path of the exported bookmarks html file",
+                    &TextField::new("").set_validation(&FieldValidation::new().set_not_empty(true)),
+                )),
             ));
 
+        let unread_count = db
+            .read_later
+            .iter()
+            .filter(|item| item.state == ReadState::Unread)
+            .count();
+
+        let read_later_result = SearchResult::new("Read Later")
+            .set_description(&format!(
+                "Save a page for later, {unread_count} unread. Use the rl keyword to see them"
+            ))
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("plus"))
+            .set_action(&ResultAction::new_open_form_action(&get_read_later_form()));
+
         results.push(add_bookmark_result);
+        results.push(read_later_result);
+        results.push(import_bookmarks_result);
 
         if !db.bookmarks.is_empty() {
             let add_group_result = SearchResult::new("Add Group")
@@ -61,199 +181,1384 @@ pub fn handle_results(request: GetResultsRequest) {
                             &TextField::new("")
                                 .set_validation(&FieldValidation::new().set_not_empty(true)),
                         ))
-                        .add_fields(
-                            &db.bookmarks
-                                .iter()
-                                .map(|bookmark| {
-                                    Field::new_switch_field(
-                                        &bookmark.id.to_string(),
-                                        &bookmark.name,
-                                        "Select the bookmark if you want it in the group",
-                                        &SwitchField::new(false),
-                                    )
-                                })
-                                .collect(),
+                        .add_field(&Field::new_text_field(
+                            "filter",
+                            "Bookmarks",
+                            "Starts with the bookmarks whose name, link or tags have these words. Use the g keyword to add more",
+                            &TextField::new(""),
+                        ))
+                        .add_field(&get_private_field(false))
+                        .add_field(&get_rule_field(""))
+                        .add_fields(&get_launch_fields(&LaunchOptions::default()))
+                        .add_fields(&get_group_fields(&db, None)),
+                ));
+
+            results.push(add_group_result);
+        }
+
+        let settings = store.settings();
+
+        let settings_result = SearchResult::new("Settings")
+            .set_description("Change the bookmarks settings")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("settings"))
+            .set_action(&ResultAction::new_open_form_action(
+                &OpenFormAction::new("bookmarks", "edit-settings", "Settings", "Save")
+                    .add_field(&Field::new_select_field(
+                        "storage",
+                        "Storage",
+                        "SQLite is faster with thousands of bookmarks and the encrypted file hides them on disk",
+                        &SelectField::new(
+                            match settings.storage {
+                                StorageBackend::Json => "json",
+                                StorageBackend::Sqlite => "sqlite",
+                                StorageBackend::Encrypted => "encrypted",
+                            },
+                            &vec![
+                                SelectFieldValue::new("json", "JSON File"),
+                                SelectFieldValue::new("sqlite", "SQLite"),
+                                SelectFieldValue::new("encrypted", "Encrypted JSON File"),
+                            ],
                         ),
+                    ))
+                    .add_field(&Field::new_text_field(
+                        "trash_days",
+                        "Trash Days",
+                        "The days deleted items stay in the trash",
+                        &TextField::new(&settings.trash_days.to_string())
+                            .set_validation(&FieldValidation::new().set_not_empty(true)),
+                    ))
+                    .add_field(&Field::new_switch_field(
+                        "git_sync",
+                        "Git Sync",
+                        "Commit every change to a git repository in the config directory",
+                        &SwitchField::new(settings.git_sync),
+                    ))
+                    .add_field(&Field::new_text_field(
+                        "git_remote",
+                        "Git Remote",
+                        "The repository the bookmarks are pulled from and pushed to",
+                        &TextField::new(&settings.git_remote),
+                    ))
+                    .add_field(&Field::new_text_field(
+                        "http_sync_url",
+                        "HTTP Sync Url",
+                        "A WebDAV or HTTP url the bookmarks are downloaded from and uploaded to",
+                        &TextField::new(&settings.http_sync_url),
+                    )),
+            ));
+
+        let check_db_result = SearchResult::new("Check Database")
+            .set_description("Find and repair broken references in the bookmarks")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("settings"))
+            .set_action(&ResultAction::new_run_extension_action(
+                &RunExtensionAction::new("bookmarks", "check-db"),
+            ));
+
+        let add_collection_result = SearchResult::new("Subscribe to Collection")
+            .set_description("Show the bookmarks shared in a file or url")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("link"))
+            .set_action(&ResultAction::new_open_form_action(
+                &OpenFormAction::new(
+                    "bookmarks",
+                    "add-collection",
+                    "Subscribe to Collection",
+                    "Subscribe",
+                )
+                .add_field(&Field::new_text_field(
+                    "name",
+                    "Name",
+                    "The name shown next to its bookmarks",
+                    &TextField::new("").set_validation(&FieldValidation::new().set_not_empty(true)),
+                ))
+                .add_field(&Field::new_text_field(
+                    "source",
+                    "Source",
+                    "The path or url of a json or html bookmarks file",
+                    &TextField::new("").set_validation(&FieldValidation::new().set_not_empty(true)),
+                ))
+                .add_field(&Field::new_text_field(
+                    "refresh_minutes",
+                    "Refresh Minutes",
+                    "How often the collection is downloaded again",
+                    &TextField::new("60"),
+                )),
+            ));
+
+        results.push(settings_result);
+        results.push(check_db_result);
+
+        if settings.git_sync || !settings.http_sync_url.trim().is_empty() {
+            let sync_result = SearchResult::new("Sync Now")
+                .set_description("Pull and push the bookmarks")
+                .set_icon_color("accent")
+                .set_icon_path(&get_icon_path("restore"))
+                .set_action(&ResultAction::new_run_extension_action(
+                    &RunExtensionAction::new("bookmarks", "sync-now"),
                 ));
 
-            let delete_bookmark_result = SearchResult::new("Delete a Bookmark")
-                .set_description("Delete a bookmark")
+            let sync_status_result = SearchResult::new("Sync Status")
+                .set_description(&describe_sync_state(&get_sync_state(dirs)))
+                .set_icon_color("accent")
+                .set_icon_path(&get_icon_path("restore"))
+                .set_action(&ResultAction::new_run_extension_action(
+                    &RunExtensionAction::new("bookmarks", "sync-now"),
+                ));
+
+            results.push(sync_result);
+            results.push(sync_status_result);
+        }
+        results.push(add_collection_result);
+
+        if !settings.collections.is_empty() {
+            let remove_collection_result = SearchResult::new("Unsubscribe from Collection")
+                .set_description("Stop showing the bookmarks of a collection")
                 .set_icon_color("accent")
                 .set_icon_path(&get_icon_path("trash"))
                 .set_action(&ResultAction::new_open_form_action(
                     &OpenFormAction::new(
                         "bookmarks",
-                        "delete-bookmark",
-                        "Delete Bookmark",
-                        "Delete Bookmark",
+                        "remove-collection",
+                        "Unsubscribe from Collection",
+                        "Unsubscribe",
                     )
                     .add_field(&Field::new_select_field(
-                        "bookmark",
-                        "Bookmark",
-                        "Select the bookmark you wish to delete",
+                        "collection",
+                        "Collection",
+                        "Select the collection you wish to unsubscribe from",
                         &SelectField::new(
-                            &db.bookmarks.first().unwrap().id.to_string(),
-                            &db.bookmarks
+                            &settings.collections.first().unwrap().name,
+                            &settings
+                                .collections
                                 .iter()
-                                .map(|bookmark| {
-                                    SelectFieldValue::new(&bookmark.id.to_string(), &bookmark.name)
+                                .map(|collection| {
+                                    SelectFieldValue::new(&collection.name, &collection.name)
                                 })
                                 .collect(),
                         ),
                     )),
                 ));
 
-            results.push(add_group_result);
-            results.push(delete_bookmark_result);
+            results.push(remove_collection_result);
         }
 
-        if !db.groups.is_empty() {
-            let delete_group_result = SearchResult::new("Delete Group")
-                .set_description("Delete a Group")
-                .set_icon_color("accent")
-                .set_icon_path(&get_icon_path("trash"))
-                .set_action(&ResultAction::new_open_form_action(
-                    &OpenFormAction::new(
-                        "bookmarks",
-                        "delete-group",
-                        "Delete Group",
-                        "Delete Group",
-                    )
+        let active_profile = &dirs.profile;
+        let profiles = get_profiles(&dirs.data_dir);
+
+        let switch_profile_result = SearchResult::new("Switch Profile")
+            .set_description(&format!("Using the {active_profile} profile"))
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("folder"))
+            .set_action(&ResultAction::new_open_form_action(
+                &OpenFormAction::new("bookmarks", "switch-profile", "Switch Profile", "Switch")
                     .add_field(&Field::new_select_field(
-                        "group",
-                        "Group",
-                        "Select the group you wish to delete",
+                        "profile",
+                        "Profile",
+                        "Select the profile you wish to use",
                         &SelectField::new(
-                            &db.groups.first().unwrap().id.to_string(),
-                            &db.groups
+                            active_profile,
+                            &profiles
                                 .iter()
-                                .map(|group| {
-                                    SelectFieldValue::new(&group.id.to_string(), &group.name)
-                                })
+                                .map(|profile| SelectFieldValue::new(profile, profile))
                                 .collect(),
                         ),
                     )),
-                ));
+            ));
 
-            results.push(delete_group_result);
-        }
+        let add_profile_result = SearchResult::new("Add Profile")
+            .set_description("Create a profile with its own bookmarks and switch to it")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("plus"))
+            .set_action(&ResultAction::new_open_form_action(
+                &OpenFormAction::new("bookmarks", "add-profile", "Add Profile", "Add Profile")
+                    .add_field(&Field::new_text_field(
+                        "name",
+                        "Name",
+                        "The profile name, like work or personal",
+                        &TextField::new("")
+                            .set_validation(&FieldValidation::new().set_not_empty(true)),
+                    )),
+            ));
 
-        return_search_results(&results);
+        results.push(switch_profile_result);
+        results.push(add_profile_result);
+
+        return results;
     }
 
     if let Some(keyword) = keyword {
-        if &keyword == "e" || &keyword == "edit" {
-            let mut edit_bookmark_results = db
+        if &keyword == "t" || &keyword == "trash" {
+            let mut restore_bookmark_results = db
+                .trash
                 .bookmarks
                 .iter()
-                .filter(|bookmark| sniffer.matches(&bookmark.name, &search_text))
-                .map(|bookmark| {
-                    SearchResult::new(&format!("Edit {}", &bookmark.name))
-                        .set_description("Edit the bookmark name and url")
+                .filter(|trashed| {
+                    search_text.is_empty() || sniffer.matches(&trashed.bookmark.name, &search_text)
+                })
+                .map(|trashed| {
+                    SearchResult::new(&format!("Restore {}", &trashed.bookmark.name))
+                        .set_description(&trashed.bookmark.link)
+                        .set_icon_color("accent")
+                        .set_icon_path(&get_icon_path("restore"))
+                        .set_action(&ResultAction::new_run_extension_action(
+                            &RunExtensionAction::new("bookmarks", "restore-bookmark")
+                                .add_arg(&trashed.bookmark.id),
+                        ))
+                })
+                .collect::<Vec<SearchResult>>();
+
+            let mut restore_group_results = db
+                .trash
+                .groups
+                .iter()
+                .filter(|trashed| {
+                    search_text.is_empty() || sniffer.matches(&trashed.group.name, &search_text)
+                })
+                .map(|trashed| {
+                    SearchResult::new(&format!("Restore {}", &trashed.group.name))
+                        .set_description("Restore the group")
+                        .set_icon_color("accent")
+                        .set_icon_path(&get_icon_path("restore"))
+                        .set_action(&ResultAction::new_run_extension_action(
+                            &RunExtensionAction::new("bookmarks", "restore-group")
+                                .add_arg(&trashed.group.id),
+                        ))
+                })
+                .collect::<Vec<SearchResult>>();
+
+            results.append(&mut restore_bookmark_results);
+            results.append(&mut restore_group_results);
+
+            if !db.trash.bookmarks.is_empty() || !db.trash.groups.is_empty() {
+                let empty_trash_result = SearchResult::new("Empty Trash")
+                    .set_description("Delete everything in the trash for good")
+                    .set_icon_color("accent")
+                    .set_icon_path(&get_icon_path("trash"))
+                    .set_action(&ResultAction::new_run_extension_action(
+                        &RunExtensionAction::new("bookmarks", "empty-trash"),
+                    ));
+
+                results.push(empty_trash_result);
+            }
+
+            return results;
+        }
+
+        if &keyword == "rl" {
+            let mut unread_items = db
+                .read_later
+                .iter()
+                .filter(|item| item.state == ReadState::Unread)
+                .filter(|item| {
+                    search_text.is_empty()
+                        || sniffer.matches(&item.title, &search_text)
+                        || sniffer.matches(&item.link, &search_text)
+                })
+                .collect::<Vec<&ReadLaterItem>>();
+
+            // Oldest first, so the queue gets worked through in order
+            unread_items.sort_by_key(|item| item.added_at);
+
+            let mut open_results = unread_items
+                .iter()
+                .map(|item| {
+                    let description = match item.reading_minutes {
+                        Some(minutes) => format!("{minutes} min read · {}", &item.link),
+                        None => item.link.to_owned(),
+                    };
+
+                    SearchResult::new(&item.title)
+                        .set_description(&description)
+                        .set_icon_color("accent")
+                        .set_icon_path(&get_icon_path("link"))
+                        .set_action(&ResultAction::new_run_extension_action(
+                            &RunExtensionAction::new("bookmarks", "open-read-later")
+                                .add_arg(&item.id),
+                        ))
+                })
+                .collect::<Vec<SearchResult>>();
+
+            let mut archive_results = unread_items
+                .iter()
+                .map(|item| {
+                    SearchResult::new(&format!("Archive {}", &item.title))
+                        .set_description("Remove it from the queue without opening it")
+                        .set_icon_color("accent")
+                        .set_icon_path(&get_icon_path("trash"))
+                        .set_action(&ResultAction::new_run_extension_action(
+                            &RunExtensionAction::new("bookmarks", "archive-read-later")
+                                .add_arg(&item.id),
+                        ))
+                })
+                .collect::<Vec<SearchResult>>();
+
+            results.append(&mut open_results);
+            results.append(&mut archive_results);
+
+            let add_result = SearchResult::new("Add to Read Later")
+                .set_description("Save a page for later")
+                .set_icon_color("accent")
+                .set_icon_path(&get_icon_path("plus"))
+                .set_action(&ResultAction::new_open_form_action(&get_read_later_form()));
+
+            results.push(add_result);
+
+            return results;
+        }
+
+        // `add My Site https://example.com` saves a bookmark without opening a form
+        if &keyword == "add" {
+            let mut words = search_text.split_whitespace().collect::<Vec<&str>>();
+            let link = words.last().and_then(|word| parse_link(word));
+
+            if link.is_some() {
+                words.pop();
+            }
+
+            let name = words.join(" ");
+
+            let add_result = match link {
+                Some(link) => {
+                    let title = match name.is_empty() {
+                        true => format!("Add {link} as bookmark"),
+                        false => format!("Add {name}"),
+                    };
+
+                    SearchResult::new(&title).set_description(&link).set_action(
+                        &ResultAction::new_run_extension_action(
+                            &RunExtensionAction::new("bookmarks", "add-bookmark")
+                                .add_arg(&link)
+                                .add_arg(&name),
+                        ),
+                    )
+                }
+                None => SearchResult::new("Add Bookmark")
+                    .set_description("Type a name followed by the link")
+                    .set_action(&ResultAction::new_open_form_action(&get_add_bookmark_form(
+                        &name, "",
+                    ))),
+            }
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("plus"));
+
+            results.push(add_result);
+
+            return results;
+        }
+
+        // Editing membership one bookmark at a time, like `g grafana infra`. Without search text
+        // it lists what's in the groups already.
+        if &keyword == "g" || &keyword == "group" {
+            for group in &db.groups {
+                for bookmark in &db.bookmarks {
+                    let in_group = group.bookmarks_ids.contains(&bookmark.id);
+
+                    let matches = match search_text.is_empty() {
+                        true => in_group,
+                        false => sniffer
+                            .matches(&format!("{} {}", &bookmark.name, &group.name), &search_text),
+                    };
+
+                    if !matches {
+                        continue;
+                    }
+
+                    let membership_result = match in_group {
+                        true => SearchResult::new(&format!(
+                            "Remove {} from {}",
+                            &bookmark.name, &group.name
+                        ))
+                        .set_icon_path(&get_icon_path("trash"))
+                        .set_action(
+                            &ResultAction::new_run_extension_action(
+                                &RunExtensionAction::new("bookmarks", "remove-from-group")
+                                    .add_arg(&group.id)
+                                    .add_arg(&bookmark.id),
+                            ),
+                        ),
+                        false => {
+                            SearchResult::new(&format!("Add {} to {}", &bookmark.name, &group.name))
+                                .set_icon_path(&get_icon_path("plus"))
+                                .set_action(&ResultAction::new_run_extension_action(
+                                    &RunExtensionAction::new("bookmarks", "add-to-group")
+                                        .add_arg(&group.id)
+                                        .add_arg(&bookmark.id),
+                                ))
+                        }
+                    }
+                    .set_description(&bookmark.link)
+                    .set_icon_color("accent");
+
+                    results.push(membership_result);
+                }
+            }
+
+            return results;
+        }
+
+        if &keyword == "a" || &keyword == "archive" {
+            for bookmark in db.bookmarks.iter().filter(|bookmark| {
+                search_text.is_empty() || matches_bookmark(&sniffer, bookmark, &search_text)
+            }) {
+                let archive_result = match bookmark.archived_at {
+                    Some(archived_at) => {
+                        SearchResult::new(&format!("Archive {} again", &bookmark.name))
+                            .set_description(&format!(
+                                "Last archived {}",
+                                describe_elapsed(archived_at)
+                            ))
+                    }
+                    None => SearchResult::new(&format!("Archive {}", &bookmark.name))
+                        .set_description("Save an offline copy of the page"),
+                }
+                .set_icon_color("accent")
+                .set_icon_path(&get_icon_path("plus"))
+                .set_action(&ResultAction::new_run_extension_action(
+                    &RunExtensionAction::new("bookmarks", "archive-bookmark").add_arg(&bookmark.id),
+                ));
+
+                if bookmark.archived_at.is_some() {
+                    results.push(get_open_archive_result(bookmark));
+                }
+
+                results.push(archive_result);
+            }
+
+            return results;
+        }
+
+        if &keyword == "p" || &keyword == "profile" {
+            let active_profile = &dirs.profile;
+
+            let mut profile_results = get_profiles(&dirs.data_dir)
+                .iter()
+                .filter(|profile| search_text.is_empty() || sniffer.matches(profile, &search_text))
+                .map(|profile| {
+                    let description = if profile == active_profile {
+                        "The profile in use"
+                    } else {
+                        "Switch to this profile"
+                    };
+
+                    SearchResult::new(profile)
+                        .set_description(description)
+                        .set_icon_color("accent")
+                        .set_icon_path(&get_icon_path("folder"))
+                        .set_action(&ResultAction::new_run_extension_action(
+                            &RunExtensionAction::new("bookmarks", "switch-profile")
+                                .add_arg(profile),
+                        ))
+                })
+                .collect::<Vec<SearchResult>>();
+
+            results.append(&mut profile_results);
+
+            return results;
+        }
+
+        if &keyword == "all" {
+            for profile in get_profiles(&dirs.data_dir) {
+                let profile_dirs = dirs.with_profile(&profile);
+
+                // Only reads the other profiles, so their sync and trash purge run when they're
+                // used. A profile that can't be read is left out.
+                let mut profile_db = match peek_db(&profile_dirs) {
+                    Ok(Some(profile_db)) => profile_db,
+                    _ => continue,
+                };
+
+                // The ids of a db from an older version only stay once the profile is used, so
+                // until then its links open directly
+                let is_migrated = profile_db.migrate().is_some();
+
+                order.sort_bookmarks(&mut profile_db.bookmarks);
+
+                let mut profile_results = profile_db
+                    .bookmarks
+                    .iter()
+                    .filter(|bookmark| !bookmark.private)
+                    .filter(|bookmark| {
+                        search_text.is_empty() || matches_bookmark(&sniffer, bookmark, &search_text)
+                    })
+                    .map(|bookmark| {
+                        let action = match is_migrated {
+                            true => ResultAction::new_open_link_action(&OpenLinkAction::new(
+                                &bookmark.link,
+                            )),
+                            false => ResultAction::new_run_extension_action(
+                                &RunExtensionAction::new("bookmarks", "open-bookmark")
+                                    .add_arg(&bookmark.id)
+                                    .add_arg(&profile),
+                            ),
+                        };
+
+                        SearchResult::new(&bookmark.name)
+                            .set_description(&format!("[{profile}] {}", &bookmark.link))
+                            .set_action(&action)
+                            .set_icon_path(&get_favicon_path(&profile_dirs, &bookmark.id))
+                    })
+                    .collect::<Vec<SearchResult>>();
+
+                results.append(&mut profile_results);
+            }
+
+            return results;
+        }
+
+        if &keyword == "b" || &keyword == "bulk" {
+            let selected_bookmarks = db
+                .bookmarks
+                .iter()
+                .filter(|bookmark| {
+                    search_text.is_empty() || matches_bookmark(&sniffer, bookmark, &search_text)
+                })
+                .collect::<Vec<&Bookmark>>();
+
+            if !selected_bookmarks.is_empty() {
+                let bookmark_switches = selected_bookmarks
+                    .iter()
+                    .map(|bookmark| {
+                        Field::new_switch_field(
+                            &bookmark.id,
+                            &bookmark.name,
+                            &bookmark.link,
+                            &SwitchField::new(false),
+                        )
+                    })
+                    .collect::<Vec<Field>>();
+
+                let bulk_delete_result = SearchResult::new("Delete Bookmarks")
+                    .set_description("Move several bookmarks to the trash")
+                    .set_icon_color("accent")
+                    .set_icon_path(&get_icon_path("trash"))
+                    .set_action(&ResultAction::new_open_form_action(
+                        &OpenFormAction::new(
+                            "bookmarks",
+                            "bulk-delete",
+                            "Delete Bookmarks",
+                            "Delete",
+                        )
+                        .add_fields(&bookmark_switches),
+                    ));
+
+                let bulk_tag_result = SearchResult::new("Tag Bookmarks")
+                    .set_description("Add tags to several bookmarks")
+                    .set_icon_color("accent")
+                    .set_icon_path(&get_icon_path("pencil"))
+                    .set_action(&ResultAction::new_open_form_action(
+                        &OpenFormAction::new("bookmarks", "bulk-tag", "Tag Bookmarks", "Tag")
+                            .add_field(&Field::new_text_field(
+                                "tags",
+                                "Tags",
+                                "The tags separated by commas",
+                                &TextField::new("")
+                                    .set_validation(&FieldValidation::new().set_not_empty(true)),
+                            ))
+                            .add_fields(&bookmark_switches),
+                    ));
+
+                let bulk_move_result = SearchResult::new("Move Bookmarks")
+                    .set_description("Move several bookmarks to a folder")
+                    .set_icon_color("accent")
+                    .set_icon_path(&get_icon_path("folder"))
+                    .set_action(&ResultAction::new_open_form_action(
+                        &OpenFormAction::new("bookmarks", "bulk-move", "Move Bookmarks", "Move")
+                            .add_field(&Field::new_text_field(
+                                "folder",
+                                "Folder",
+                                "The folder name. Leave it empty to remove them from their folder",
+                                &TextField::new(""),
+                            ))
+                            .add_fields(&bookmark_switches),
+                    ));
+
+                results.push(bulk_delete_result);
+                results.push(bulk_tag_result);
+                results.push(bulk_move_result);
+
+                if !db.groups.is_empty() {
+                    let group_select = SelectField::new(
+                        &db.groups.first().unwrap().id,
+                        &db.groups
+                            .iter()
+                            .map(|group| SelectFieldValue::new(&group.id, &group.name))
+                            .collect(),
+                    );
+
+                    let bulk_add_to_group_result = SearchResult::new("Add Bookmarks to a Group")
+                        .set_description("Add several bookmarks to a group")
                         .set_icon_color("accent")
-                        .set_icon_path(&get_icon_path("pencil"))
+                        .set_icon_path(&get_icon_path("plus"))
                         .set_action(&ResultAction::new_open_form_action(
                             &OpenFormAction::new(
                                 "bookmarks",
-                                "edit-bookmark",
-                                "Edit Bookmark",
-                                "Save",
+                                "bulk-add-to-group",
+                                "Add Bookmarks to a Group",
+                                "Add",
                             )
-                            .add_arg(&bookmark.id.to_string())
-                            .add_field(&Field::new_text_field(
-                                "name",
-                                "Name",
-                                "The bookmark name",
-                                &TextField::new(&bookmark.name)
-                                    .set_validation(&FieldValidation::new().set_not_empty(true)),
+                            .add_field(&Field::new_select_field(
+                                "group",
+                                "Group",
+                                "The group to add the bookmarks to",
+                                &group_select,
                             ))
-                            .add_field(&Field::new_text_field(
-                                "link",
-                                "Link",
-                                "The bookmark link",
-                                &TextField::new(&bookmark.link)
-                                    .set_validation(&FieldValidation::new().set_not_empty(true)),
-                            )),
-                        ))
-                })
+                            .add_fields(&bookmark_switches),
+                        ));
+
+                    let bulk_remove_from_group_result =
+                        SearchResult::new("Remove Bookmarks from a Group")
+                            .set_description("Remove several bookmarks from a group")
+                            .set_icon_color("accent")
+                            .set_icon_path(&get_icon_path("trash"))
+                            .set_action(&ResultAction::new_open_form_action(
+                                &OpenFormAction::new(
+                                    "bookmarks",
+                                    "bulk-remove-from-group",
+                                    "Remove Bookmarks from a Group",
+                                    "Remove",
+                                )
+                                .add_field(&Field::new_select_field(
+                                    "group",
+                                    "Group",
+                                    "The group to remove the bookmarks from",
+                                    &group_select,
+                                ))
+                                .add_fields(&bookmark_switches),
+                            ));
+
+                    results.push(bulk_add_to_group_result);
+                    results.push(bulk_remove_from_group_result);
+                }
+            }
+
+            return results;
+        }
+
+        if &keyword == "d" || &keyword == "delete" {
+            let mut delete_bookmark_results = db
+                .bookmarks
+                .iter()
+                .filter(|bookmark| matches_bookmark(&sniffer, bookmark, &search_text))
+                .map(get_delete_bookmark_result)
+                .collect::<Vec<SearchResult>>();
+
+            let mut delete_group_results = db
+                .groups
+                .iter()
+                .filter(|group| sniffer.matches(&group.name, &search_text))
+                .map(get_delete_group_result)
+                .collect::<Vec<SearchResult>>();
+
+            results.append(&mut delete_bookmark_results);
+            results.append(&mut delete_group_results);
+
+            return results;
+        }
+
+        // Everything a bookmark result could do besides opening, like `m grafana`
+        if &keyword == "m" || &keyword == "more" {
+            for bookmark in db
+                .bookmarks
+                .iter()
+                .filter(|bookmark| matches_bookmark(&sniffer, bookmark, &search_text))
+            {
+                results.append(&mut get_bookmark_action_results(bookmark, &db));
+            }
+
+            return results;
+        }
+
+        if &keyword == "e" || &keyword == "edit" {
+            let mut edit_bookmark_results = db
+                .bookmarks
+                .iter()
+                .filter(|bookmark| sniffer.matches(&bookmark.name, &search_text))
+                .map(get_edit_bookmark_result)
                 .collect::<Vec<SearchResult>>();
 
             let mut edit_group_results = db
                 .groups
                 .iter()
                 .filter(|group| sniffer.matches(&group.name, &search_text))
-                .map(|group| {
-                    SearchResult::new(&format!("Edit {}", &group.name))
-                        .set_description("Edit the group name and bookmarks")
-                        .set_icon_color("accent")
-                        .set_icon_path(&get_icon_path("pencil"))
-                        .set_action(&ResultAction::new_open_form_action(
-                            &OpenFormAction::new("bookmarks", "edit-group", "Edit Group", "Save")
-                                .add_arg(&group.id.to_string())
-                                .add_field(&Field::new_text_field(
-                                    "name",
-                                    "Name",
-                                    "The group name",
-                                    &TextField::new(&group.name),
-                                ))
-                                .add_fields(
-                                    &db.bookmarks
-                                        .iter()
-                                        .map(|bookmark| {
-                                            Field::new_switch_field(
-                                                &bookmark.id.to_string(),
-                                                &bookmark.name,
-                                                "Select the bookmark if you want it in the group",
-                                                &SwitchField::new(
-                                                    group.bookmarks_ids.contains(&bookmark.id),
-                                                ),
-                                            )
-                                        })
-                                        .collect(),
-                                ),
-                        ))
-                })
+                .map(|group| get_edit_group_result(group, &db))
                 .collect::<Vec<SearchResult>>();
 
             results.append(&mut edit_bookmark_results);
             results.append(&mut edit_group_results);
 
-            return_search_results(&results);
+            return results;
+        }
+
+        if is_private_keyword {
+            let private_bookmarks = db
+                .bookmarks
+                .iter()
+                .filter(|bookmark| bookmark.private)
+                .filter(|bookmark| {
+                    search_text.is_empty() || matches_bookmark(&sniffer, bookmark, &search_text)
+                })
+                .collect::<Vec<&Bookmark>>();
+
+            let private_groups = db
+                .groups
+                .iter()
+                .filter(|group| group.private)
+                .filter(|group| {
+                    search_text.is_empty() || sniffer.matches(&group.name, &search_text)
+                })
+                .collect::<Vec<&Group>>();
+
+            for bookmark in &private_bookmarks {
+                let bookmark_result = SearchResult::new(&bookmark.name)
+                    .set_description(&bookmark.link)
+                    .set_icon_color("accent")
+                    .set_icon_path(&get_icon_path("link"))
+                    .set_action(&ResultAction::new_run_extension_action(
+                        &RunExtensionAction::new("bookmarks", "open-bookmark")
+                            .add_arg(&bookmark.id),
+                    ));
+
+                results.push(bookmark_result);
+            }
+
+            for group in &private_groups {
+                let group_result = SearchResult::new(&group.name)
+                    .set_description(&get_group_description(group, &db))
+                    .set_icon_color("accent")
+                    .set_icon_path(&get_icon_path("folder"))
+                    .set_action(&get_open_group_action(group, &db));
+
+                results.push(group_result);
+            }
+
+            for bookmark in &private_bookmarks {
+                results.push(get_edit_bookmark_result(bookmark));
+            }
+
+            for group in &private_groups {
+                results.push(get_edit_group_result(group, &db));
+            }
+
+            for bookmark in &private_bookmarks {
+                results.push(get_delete_bookmark_result(bookmark));
+            }
+
+            for group in &private_groups {
+                results.push(get_delete_group_result(group));
+            }
+
+            return results;
         }
     }
 
-    let mut bookmarks = db
-        .bookmarks
+    // The sqlite search index also matches links and ranks its hits first. Typos only match with
+    // the fuzzy search, so its matches follow.
+    let search_hits = match search_text.is_empty() {
+        true => vec![],
+        false => store.search(&search_text).unwrap_or_default(),
+    };
+
+    let mut matching_bookmarks = search_hits
+        .iter()
+        .filter_map(|id| db.bookmarks.iter().find(|bookmark| &bookmark.id == id))
+        .collect::<Vec<&Bookmark>>();
+
+    matching_bookmarks.extend(db.bookmarks.iter().filter(|bookmark| {
+        !search_hits.contains(&bookmark.id)
+            && (search_text.is_empty() || matches_bookmark(&sniffer, bookmark, &search_text))
+    }));
+
+    let mut bookmarks = matching_bookmarks
         .iter()
-        .filter(|bookmark| sniffer.matches(&bookmark.name, &search_text))
         .map(|bookmark| {
             SearchResult::new(&bookmark.name)
                 .set_description(&bookmark.link)
-                .set_action(&ResultAction::new_open_link_action(&OpenLinkAction::new(
-                    &bookmark.link,
-                )))
-                .set_icon_path(&get_favicon_path(&bookmark.id.to_string()))
+                .set_action(&ResultAction::new_run_extension_action(
+                    &RunExtensionAction::new("bookmarks", "open-bookmark").add_arg(&bookmark.id),
+                ))
+                .set_icon_path(&get_favicon_path(dirs, &bookmark.id))
         })
         .collect::<Vec<SearchResult>>();
 
+    // Once the search narrows down to one bookmark its other actions show up under it
+    if !search_text.is_empty() && matching_bookmarks.len() == 1 {
+        bookmarks.append(&mut get_bookmark_action_results(matching_bookmarks[0], &db));
+    }
+
     let mut groups = db
         .groups
         .iter()
-        .filter(|group| sniffer.matches(&group.name, &search_text))
+        .filter(|group| search_text.is_empty() || sniffer.matches(&group.name, &search_text))
         .map(|group| {
             SearchResult::new(&group.name)
-                .set_description("Open the group")
+                .set_description(&get_group_description(group, &db))
                 .set_icon_color("accent")
                 .set_icon_path(&get_icon_path("folder"))
-                .set_action(&ResultAction::new_run_extension_action(
-                    &RunExtensionAction::new("bookmarks", "open-group")
-                        .add_arg(&group.id.to_string()),
-                ))
+                .set_action(&get_open_group_action(group, &db))
         })
         .collect::<Vec<SearchResult>>();
 
+    // Offline copies only show up when searching, so they don't double the full list
+    let mut archive_results = match search_text.is_empty() {
+        true => vec![],
+        false => db
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.archived_at.is_some())
+            .filter(|bookmark| {
+                search_hits.contains(&bookmark.id)
+                    || matches_bookmark(&sniffer, bookmark, &search_text)
+            })
+            .map(get_open_archive_result)
+            .collect::<Vec<SearchResult>>(),
+    };
+
+    // A typed link that isn't saved yet can be added right away. Links without https:// only
+    // count when nothing else matched, so searching for `notes.md` doesn't suggest a site.
+    let has_scheme = search_text.starts_with("http://") || search_text.starts_with("https://");
+
+    let add_link_result = parse_link(&search_text)
+        .filter(|_| has_scheme || (bookmarks.is_empty() && groups.is_empty()))
+        .filter(|link| !db.bookmarks.iter().any(|bookmark| &bookmark.link == link))
+        .map(|link| {
+            SearchResult::new(&format!("Add {link} as bookmark"))
+                .set_description("Save it with the page title as the name")
+                .set_icon_color("accent")
+                .set_icon_path(&get_icon_path("plus"))
+                .set_action(&ResultAction::new_run_extension_action(
+                    &RunExtensionAction::new("bookmarks", "add-bookmark").add_arg(&link),
+                ))
+        });
+
+    let mut preview_results = match search_text.is_empty() {
+        true => vec![],
+        false => db
+            .groups
+            .iter()
+            .filter(|group| sniffer.matches(&group.name, &search_text))
+            .map(|group| get_preview_group_result(group, &db))
+            .collect::<Vec<SearchResult>>(),
+    };
+
     results.append(&mut bookmarks);
     results.append(&mut groups);
+    results.append(&mut preview_results);
+    results.append(&mut archive_results);
+    results.extend(add_link_result);
+
+    if !search_text.is_empty() {
+        results.append(&mut get_collection_results(dirs, &sniffer, &search_text));
+    }
+
+    results
+}
+
+fn get_bookmark_action_results(bookmark: &Bookmark, db: &BookmarksDB) -> Vec<SearchResult> {
+    let copy_results = [
+        (LinkFormat::Link, "link"),
+        (LinkFormat::Markdown, "as Markdown"),
+        (LinkFormat::Html, "as HTML"),
+    ]
+    .into_iter()
+    .map(|(format, suffix)| {
+        SearchResult::new(&format!("Copy {} {suffix}", &bookmark.name))
+            .set_description(&format.format(bookmark))
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("link"))
+            .set_action(&ResultAction::new_run_extension_action(
+                &RunExtensionAction::new("bookmarks", "copy-bookmark")
+                    .add_arg(&bookmark.id)
+                    .add_arg(format.name()),
+            ))
+    });
+
+    let mut action_results = match bookmark.private {
+        true => vec![],
+        false => copy_results.collect::<Vec<SearchResult>>(),
+    };
 
-    return_search_results(&results);
+    action_results.push(get_edit_bookmark_result(bookmark));
+    action_results.push(get_delete_bookmark_result(bookmark));
+
+    let other_groups = db
+        .groups
+        .iter()
+        .filter(|group| !group.bookmarks_ids.contains(&bookmark.id))
+        .collect::<Vec<&Group>>();
+
+    if let Some(first_group) = other_groups.first() {
+        let add_to_group_result = SearchResult::new(&format!("Add {} to a Group", &bookmark.name))
+            .set_description("Pick the group to add it to")
+            .set_icon_color("accent")
+            .set_icon_path(&get_icon_path("folder"))
+            .set_action(&ResultAction::new_open_form_action(
+                &OpenFormAction::new(
+                    "bookmarks",
+                    "add-bookmark-to-group",
+                    "Add to Group",
+                    "Add to Group",
+                )
+                .add_arg(&bookmark.id)
+                .add_field(&Field::new_select_field(
+                    "group",
+                    "Group",
+                    "Select the group you wish to add it to",
+                    &SelectField::new(
+                        &first_group.id,
+                        &other_groups
+                            .iter()
+                            .map(|group| SelectFieldValue::new(&group.id, &group.name))
+                            .collect(),
+                    ),
+                )),
+            ));
+
+        action_results.push(add_to_group_result);
+    }
+
+    if bookmark.archived_at.is_some() {
+        action_results.push(get_open_archive_result(bookmark));
+    }
+
+    action_results
+}
+
+fn get_delete_bookmark_result(bookmark: &Bookmark) -> SearchResult {
+    SearchResult::new(&format!("Delete {}", &bookmark.name))
+        .set_description("Move the bookmark to the trash")
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("trash"))
+        .set_action(&ResultAction::new_open_form_action(
+            &OpenFormAction::new(
+                "bookmarks",
+                "delete-bookmark",
+                &format!("Delete {}", &bookmark.name),
+                "Move to Trash",
+            )
+            .add_arg(&bookmark.id),
+        ))
+}
+
+fn get_edit_bookmark_result(bookmark: &Bookmark) -> SearchResult {
+    SearchResult::new(&format!("Edit {}", &bookmark.name))
+        .set_description("Edit the bookmark name and url")
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("pencil"))
+        .set_action(&ResultAction::new_open_form_action(
+            &OpenFormAction::new("bookmarks", "edit-bookmark", "Edit Bookmark", "Save")
+                .add_arg(&bookmark.id)
+                .add_field(&Field::new_text_field(
+                    "name",
+                    "Name",
+                    "The bookmark name",
+                    &TextField::new(&bookmark.name)
+                        .set_validation(&FieldValidation::new().set_not_empty(true)),
+                ))
+                .add_field(&Field::new_text_field(
+                    "link",
+                    "Link",
+                    "The bookmark link",
+                    &TextField::new(&bookmark.link)
+                        .set_validation(&FieldValidation::new().set_not_empty(true)),
+                ))
+                .add_field(&Field::new_text_field(
+                    "tags",
+                    "Tags",
+                    "The tags separated by commas",
+                    &TextField::new(&bookmark.tags.join(", ")),
+                ))
+                .add_field(&Field::new_text_field(
+                    "folder",
+                    "Folder",
+                    "The folder the bookmark is in",
+                    &TextField::new(bookmark.folder.as_deref().unwrap_or("")),
+                ))
+                .add_field(&get_private_field(bookmark.private)),
+        ))
+}
+
+fn get_delete_group_result(group: &Group) -> SearchResult {
+    SearchResult::new(&format!("Delete {}", &group.name))
+        .set_description("Move the group to the trash. Its bookmarks are kept")
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("trash"))
+        .set_action(&ResultAction::new_open_form_action(
+            &OpenFormAction::new(
+                "bookmarks",
+                "delete-group",
+                &format!("Delete {}", &group.name),
+                "Move to Trash",
+            )
+            .add_arg(&group.id),
+        ))
+}
+
+fn get_edit_group_result(group: &Group, db: &BookmarksDB) -> SearchResult {
+    SearchResult::new(&format!("Edit {}", &group.name))
+        .set_description(
+            "Edit the group name, rule and groups. Use the g keyword for its bookmarks",
+        )
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("pencil"))
+        .set_action(&ResultAction::new_open_form_action(
+            &OpenFormAction::new("bookmarks", "edit-group", "Edit Group", "Save")
+                .add_arg(&group.id)
+                .add_field(&Field::new_text_field(
+                    "name",
+                    "Name",
+                    "The group name",
+                    &TextField::new(&group.name),
+                ))
+                .add_field(&get_private_field(group.private))
+                .add_field(&get_rule_field(group.rule.as_deref().unwrap_or_default()))
+                .add_fields(&get_launch_fields(&group.launch))
+                .add_fields(&get_group_fields(db, Some(group))),
+        ))
+}
+
+fn get_group_fields(db: &BookmarksDB, group: Option<&Group>) -> Vec<Field> {
+    db.groups
+        .iter()
+        .filter(|other| match group {
+            Some(group) => {
+                other.id != group.id && !db.creates_cycle(&group.id, &vec![other.id.to_owned()])
+            }
+            None => true,
+        })
+        .map(|other| {
+            Field::new_switch_field(
+                &format!("group:{}", &other.id),
+                &format!("{} (group)", &other.name),
+                "Select the group if you want it to open with this one",
+                &SwitchField::new(group.is_some_and(|group| group.groups_ids.contains(&other.id))),
+            )
+        })
+        .collect()
+}
+
+fn get_group_description(group: &Group, db: &BookmarksDB) -> String {
+    match &group.rule {
+        Some(rule) => format!(
+            "Opens {} bookmarks matching {rule}",
+            group.get_launch_bookmarks(&db.bookmarks, &db.groups).len()
+        ),
+        None => String::from("Open the group"),
+    }
+}
+
+fn get_open_group_action(group: &Group, db: &BookmarksDB) -> ResultAction {
+    let links_count = group.get_launch_bookmarks(&db.bookmarks, &db.groups).len();
+
+    if group.launch.needs_confirmation(links_count) {
+        return ResultAction::new_open_form_action(&get_open_group_form(group, db));
+    }
+
+    ResultAction::new_run_extension_action(
+        &RunExtensionAction::new("bookmarks", "open-group").add_arg(&group.id),
+    )
+}
+
+fn get_open_group_form(group: &Group, db: &BookmarksDB) -> OpenFormAction {
+    let launch_bookmarks = group.get_launch_bookmarks(&db.bookmarks, &db.groups);
+
+    OpenFormAction::new(
+        "bookmarks",
+        "open-group",
+        &format!("Open {}", &group.name),
+        &format!("Open {} Links", launch_bookmarks.len()),
+    )
+    .add_arg(&group.id)
+    .add_fields(
+        &launch_bookmarks
+            .iter()
+            .map(|bookmark| {
+                Field::new_switch_field(
+                    &bookmark.id,
+                    &bookmark.name,
+                    &bookmark.link,
+                    &SwitchField::new(true),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn get_preview_group_result(group: &Group, db: &BookmarksDB) -> SearchResult {
+    let launch_bookmarks = group.get_launch_bookmarks(&db.bookmarks, &db.groups);
+
+    let names = launch_bookmarks
+        .iter()
+        .map(|bookmark| bookmark.name.to_owned())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut description = format!("Opens {} links: {names}", launch_bookmarks.len());
+
+    if group.launch.delay_ms > 0 {
+        description.push_str(&format!(
+            ", {} seconds apart",
+            group.launch.delay_ms as f64 / 1000.0
+        ));
+    }
+
+    SearchResult::new(&format!("Preview {}", &group.name))
+        .set_description(&description)
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("folder"))
+        .set_action(&ResultAction::new_open_form_action(&get_open_group_form(
+            group, db,
+        )))
+}
+
+fn get_rule_field(rule: &str) -> Field {
+    Field::new_text_field(
+        "rule",
+        "Rule",
+        "Also add every bookmark matching it, like tag:infra host:*.grafana.net opened:7d",
+        &TextField::new(rule),
+    )
+}
+
+fn get_launch_fields(launch: &LaunchOptions) -> Vec<Field> {
+    let get_count = |count: Option<usize>| count.map(|count| count.to_string()).unwrap_or_default();
+
+    vec![
+        Field::new_text_field(
+            "delay",
+            "Delay",
+            "Seconds to wait between links. With 0 they all open at once",
+            &TextField::new(&(launch.delay_ms as f64 / 1000.0).to_string()),
+        ),
+        Field::new_text_field(
+            "max_tabs",
+            "Max Tabs",
+            "Only open this many links. Leave it empty to open all of them",
+            &TextField::new(&get_count(launch.max_tabs)),
+        ),
+        Field::new_text_field(
+            "confirm_above",
+            "Confirm Above",
+            "Ask before opening more links than this. Leave it empty to never ask",
+            &TextField::new(&get_count(launch.confirm_above)),
+        ),
+        Field::new_text_field(
+            "browser",
+            "Browser",
+            "The browser to open the links with, like firefox. Leave it empty for the default one",
+            &TextField::new(launch.browser.as_deref().unwrap_or_default()),
+        ),
+    ]
+}
+
+fn get_add_bookmark_form(name: &str, link: &str) -> OpenFormAction {
+    OpenFormAction::new("bookmarks", "add-bookmark", "Add Bookmark", "Add Bookmark")
+        .add_field(&Field::new_text_field(
+            "name",
+            "Name",
+            "The bookmark name. Leave it empty to use the page title",
+            &TextField::new(name),
+        ))
+        .add_field(&Field::new_text_field(
+            "link",
+            "Link",
+            "The bookmark link",
+            &TextField::new(link).set_validation(&FieldValidation::new().set_not_empty(true)),
+        ))
+        .add_field(&get_private_field(false))
+}
+
+fn get_open_archive_result(bookmark: &Bookmark) -> SearchResult {
+    let archived_at = bookmark.archived_at.unwrap_or(0);
+
+    SearchResult::new(&format!("Open archived copy of {}", &bookmark.name))
+        .set_description(&format!("Archived {}", describe_elapsed(archived_at)))
+        .set_icon_color("accent")
+        .set_icon_path(&get_icon_path("restore"))
+        .set_action(&ResultAction::new_run_extension_action(
+            &RunExtensionAction::new("bookmarks", "open-archive").add_arg(&bookmark.id),
+        ))
+}
+
+fn get_read_later_form() -> OpenFormAction {
+    OpenFormAction::new("bookmarks", "add-read-later", "Read Later", "Add").add_field(
+        &Field::new_text_field(
+            "link",
+            "Link",
+            "The page link. Its title and reading time are fetched from the page",
+            &TextField::new("").set_validation(&FieldValidation::new().set_not_empty(true)),
+        ),
+    )
+}
+
+fn get_private_field(private: bool) -> Field {
+    Field::new_switch_field(
+        "private",
+        "Private",
+        "Only show it under the private keyword and don't fetch its favicon",
+        &SwitchField::new(private),
+    )
+}
+
+fn get_collection_results(dirs: &Dirs, sniffer: &Sniffer, search_text: &str) -> Vec<SearchResult> {
+    let collections = get_settings(dirs).collections;
+
+    if collections
+        .iter()
+        .any(|collection| is_stale(dirs, collection))
+    {
+        spawn_refresh(dirs);
+    }
+
+    collections
+        .iter()
+        .filter_map(|collection| {
+            get_collection_cache(dirs, collection).map(|cache| (collection.name.to_owned(), cache))
+        })
+        .flat_map(|(collection_name, cache)| {
+            cache
+                .bookmarks
+                .into_iter()
+                .filter(|bookmark| sniffer.matches(&bookmark.name, search_text))
+                .map(move |bookmark| {
+                    SearchResult::new(&bookmark.name)
+                        .set_description(&format!("[{collection_name}] {}", &bookmark.link))
+                        .set_icon_color("accent")
+                        .set_icon_path(&get_icon_path("link"))
+                        .set_action(&ResultAction::new_open_link_action(&OpenLinkAction::new(
+                            &bookmark.link,
+                        )))
+                })
+        })
+        .collect()
+}
+
+fn matches_bookmark(sniffer: &Sniffer, bookmark: &Bookmark, search_text: &str) -> bool {
+    sniffer.matches(&bookmark.name, search_text)
+        || bookmark
+            .tags
+            .iter()
+            .any(|tag| sniffer.matches(tag, search_text))
+        || bookmark
+            .folder
+            .as_ref()
+            .is_some_and(|folder| sniffer.matches(folder, search_text))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tigris_core::features::api::GetResultsRequest;
+
+    use super::{get_results, load_results};
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB, DB_VERSION},
+        paths::{get_encrypted_db_path, get_settings_path, get_test_dirs},
+        settings::{write_settings, Settings},
+        storage::{open_store, StorageBackend},
+        store::MemoryStore,
+    };
+
+    fn count_results(bookmarks: Vec<Bookmark>, search_text: &str) -> usize {
+        let db = BookmarksDB {
+            bookmarks,
+            ..Default::default()
+        };
+        let dirs = get_test_dirs();
+        let store = MemoryStore::new(&dirs, db);
+        let request = GetResultsRequest {
+            search_text: search_text.to_owned(),
+        };
+
+        get_results(request, &store).len()
+    }
+
+    #[test]
+    fn lists_the_matching_bookmarks() {
+        let bookmarks = vec![
+            Bookmark::new("Rust Docs", "https://docs.rs"),
+            Bookmark::new("Rust Crates", "https://crates.io"),
+            Bookmark::new("Python", "https://python.org"),
+        ];
+
+        assert_eq!(count_results(bookmarks, "rust"), 2);
+    }
+
+    #[test]
+    fn hides_private_bookmarks() {
+        let public = vec![
+            Bookmark::new("Rust Docs", "https://docs.rs"),
+            Bookmark::new("Rust Crates", "https://crates.io"),
+        ];
+        let mut with_private = public.to_owned();
+        with_private.push(Bookmark {
+            private: true,
+            ..Bookmark::new("Rust Bank", "https://bank.example.com")
+        });
+
+        assert_eq!(
+            count_results(with_private, "rust"),
+            count_results(public, "rust")
+        );
+    }
+
+    #[test]
+    fn keeps_fuzzy_matches_next_to_the_search_index_hits() {
+        let dirs = get_test_dirs();
+        let settings = Settings {
+            storage: StorageBackend::Sqlite,
+            ..Default::default()
+        };
+        write_settings(&dirs, &settings);
+
+        let store = open_store(&dirs).unwrap();
+        store.write(&BookmarksDB {
+            version: DB_VERSION,
+            bookmarks: vec![
+                Bookmark::new("Rust Docs", "https://docs.rs"),
+                Bookmark::new("Trusted Sites", "https://example.com"),
+            ],
+            ..Default::default()
+        });
+
+        let request = GetResultsRequest {
+            search_text: String::from("rust"),
+        };
+
+        assert_eq!(get_results(request, store.as_ref()).len(), 2);
+    }
+
+    #[test]
+    fn shows_an_unreadable_db_as_a_result() {
+        let dirs = get_test_dirs();
+        let settings = Settings {
+            storage: StorageBackend::Encrypted,
+            ..Default::default()
+        };
+        write_settings(&dirs, &settings);
+        fs::write(get_encrypted_db_path(&dirs), "not encrypted").unwrap();
+
+        let request = GetResultsRequest {
+            search_text: String::from("rust"),
+        };
+
+        assert_eq!(
+            get_results(request, open_store(&dirs).unwrap().as_ref()).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn shows_unreadable_settings_as_a_result() {
+        let dirs = get_test_dirs();
+        fs::write(get_settings_path(&dirs), "{").unwrap();
+
+        let request = GetResultsRequest {
+            search_text: String::from("rust"),
+        };
+
+        assert_eq!(load_results(request, &dirs).len(), 1);
+    }
 }
@@ -0,0 +1,120 @@
+use regex::Regex;
+use reqwest::Url;
+
+use crate::bookmarks::{now, Bookmark};
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Tag(String),
+    Host(Regex),
+    Folder(String),
+    Opened(u64),
+    Text(String),
+}
+
+impl Rule {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let terms = text
+            .split_whitespace()
+            .map(parse_term)
+            .collect::<Result<Vec<Term>, String>>()?;
+
+        if terms.is_empty() {
+            return Err(String::from("The rule is empty"));
+        }
+
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, bookmark: &Bookmark) -> bool {
+        self.terms.iter().all(|term| term.matches(bookmark))
+    }
+}
+
+impl Term {
+    fn matches(&self, bookmark: &Bookmark) -> bool {
+        match self {
+            Term::Tag(tag) => bookmark.tags.contains(tag),
+            Term::Host(host_regex) => Url::parse(&bookmark.link)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+                .is_some_and(|host| host_regex.is_match(&host)),
+            Term::Folder(folder) => bookmark
+                .folder
+                .as_ref()
+                .is_some_and(|bookmark_folder| bookmark_folder.to_lowercase() == *folder),
+            Term::Opened(seconds) => bookmark
+                .last_opened_at
+                .is_some_and(|opened_at| opened_at >= now().saturating_sub(*seconds)),
+            Term::Text(text) => {
+                bookmark.name.to_lowercase().contains(text)
+                    || bookmark.link.to_lowercase().contains(text)
+            }
+        }
+    }
+}
+
+fn parse_term(term: &str) -> Result<Term, String> {
+    let term = term.to_lowercase();
+
+    let (key, value) = match term.split_once(':') {
+        // Links like https://example.com are text, not a key
+        Some((key, value)) if !value.starts_with("//") => (key, value),
+        _ => return Ok(Term::Text(term.to_owned())),
+    };
+
+    if value.is_empty() {
+        return Err(format!("{key}: needs a value"));
+    }
+
+    match key {
+        "tag" => Ok(Term::Tag(value.to_owned())),
+        "host" => {
+            let pattern = regex::escape(value).replace(r"\*", ".*");
+
+            Ok(Term::Host(Regex::new(&format!("^{pattern}$")).unwrap()))
+        }
+        "folder" => Ok(Term::Folder(value.to_owned())),
+        "opened" => parse_duration(value).map(Term::Opened),
+        _ => Err(format!(
+            "Unknown rule {key}:, use tag:, host:, folder: or opened:"
+        )),
+    }
+}
+
+fn parse_duration(text: &str) -> Result<u64, String> {
+    let invalid = || format!("opened:{text} isn't a duration like 30m, 12h, 7d or 4w");
+
+    let unit_seconds = match text.chars().last() {
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 24 * 60 * 60,
+        Some('w') => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    let amount = text[..text.len() - 1]
+        .parse::<u64>()
+        .map_err(|_| invalid())?;
+
+    amount
+        .checked_mul(unit_seconds)
+        .ok_or(format!("opened:{text} is too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rule;
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        assert!(Rule::parse("opened:7d").is_ok());
+        assert!(Rule::parse("opened:99999999999999999w").is_err());
+        assert!(Rule::parse("opened:7y").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    encryption::KeySource,
+    paths::{get_settings_path, Dirs},
+    storage::StorageBackend,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Settings {
+    #[serde(default)]
+    pub storage: StorageBackend,
+    #[serde(default)]
+    pub encryption_key: KeySource,
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u64,
+    #[serde(default)]
+    pub collections: Vec<CollectionSource>,
+    #[serde(default)]
+    pub git_sync: bool,
+    #[serde(default)]
+    pub git_remote: String,
+    #[serde(default = "default_git_branch")]
+    pub git_branch: String,
+    #[serde(default)]
+    pub http_sync_url: String,
+    #[serde(default)]
+    pub http_sync_username: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CollectionSource {
+    pub name: String,
+    pub source: String,
+    #[serde(default = "default_refresh_minutes")]
+    pub refresh_minutes: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            storage: StorageBackend::default(),
+            encryption_key: KeySource::default(),
+            trash_days: default_trash_days(),
+            collections: vec![],
+            git_sync: false,
+            git_remote: String::new(),
+            git_branch: default_git_branch(),
+            http_sync_url: String::new(),
+            http_sync_username: String::new(),
+        }
+    }
+}
+
+fn default_trash_days() -> u64 {
+    30
+}
+
+fn default_refresh_minutes() -> u64 {
+    60
+}
+
+fn default_git_branch() -> String {
+    String::from("main")
+}
+
+pub fn get_settings(dirs: &Dirs) -> Settings {
+    read_settings(dirs).unwrap_or_else(|error| panic!("Error reading settings: {error}"))
+}
+
+pub fn read_settings(dirs: &Dirs) -> Result<Settings, String> {
+    let settings_path = get_settings_path(dirs);
+
+    if !settings_path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let settings_json = fs::read_to_string(&settings_path).map_err(|error| error.to_string())?;
+    serde_json::from_str::<Settings>(&settings_json).map_err(|error| error.to_string())
+}
+
+pub fn write_settings(dirs: &Dirs, settings: &Settings) {
+    let config_dir = dirs.profile_dir();
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).expect("Error creating bookmarks directory");
+    }
+
+    let json = serde_json::to_string(settings).expect("Error serializing settings");
+    fs::write(get_settings_path(dirs), &json).expect("Error writing settings");
+}
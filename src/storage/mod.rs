@@ -0,0 +1,958 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{params, Connection, OpenFlags, Transaction};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::{Bookmark, BookmarksDB, TrashedBookmark, TrashedGroup, DB_VERSION},
+    encryption::{get_key_info, new_store_id, Cipher, KeySource, PASSPHRASE_VAR},
+    paths::{
+        get_archives_dir, get_db_path, get_encrypted_db_path, get_git_sync_dir, get_sqlite_db_path,
+        Dirs,
+    },
+    settings::{get_settings, read_settings, Settings},
+    store::BookmarkStore,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Json,
+    Sqlite,
+    Encrypted,
+}
+
+pub struct JsonStore {
+    dirs: Dirs,
+    path: PathBuf,
+}
+
+pub struct EncryptedStore {
+    dirs: Dirs,
+    path: PathBuf,
+    key_source: KeySource,
+    passphrase: Option<String>,
+}
+
+pub struct SqliteStore {
+    dirs: Dirs,
+    path: PathBuf,
+}
+
+const SCHEMA_VERSION: u32 = 2;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+
+    CREATE TABLE IF NOT EXISTS bookmarks (
+        id TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        link TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        folder TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        private INTEGER NOT NULL DEFAULT 0,
+        last_opened_at INTEGER,
+        archived_at INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS bookmarks_link ON bookmarks (link);
+    CREATE INDEX IF NOT EXISTS bookmarks_updated_at ON bookmarks (updated_at);
+
+    CREATE TABLE IF NOT EXISTS groups (id TEXT PRIMARY KEY, position INTEGER NOT NULL, data TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS trash (id TEXT PRIMARY KEY, position INTEGER NOT NULL, data TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS tombstones (id TEXT PRIMARY KEY, position INTEGER NOT NULL, data TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS read_later (id TEXT PRIMARY KEY, position INTEGER NOT NULL, data TEXT NOT NULL);
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS bookmarks_search USING fts5 (
+        name, link, tags, folder, content = 'bookmarks', content_rowid = 'rowid'
+    );
+    CREATE TRIGGER IF NOT EXISTS bookmarks_search_insert AFTER INSERT ON bookmarks BEGIN
+        INSERT INTO bookmarks_search (rowid, name, link, tags, folder)
+        VALUES (new.rowid, new.name, new.link, new.tags, new.folder);
+    END;
+    CREATE TRIGGER IF NOT EXISTS bookmarks_search_delete AFTER DELETE ON bookmarks BEGIN
+        INSERT INTO bookmarks_search (bookmarks_search, rowid, name, link, tags, folder)
+        VALUES ('delete', old.rowid, old.name, old.link, old.tags, old.folder);
+    END;
+    CREATE TRIGGER IF NOT EXISTS bookmarks_search_update AFTER UPDATE ON bookmarks BEGIN
+        INSERT INTO bookmarks_search (bookmarks_search, rowid, name, link, tags, folder)
+        VALUES ('delete', old.rowid, old.name, old.link, old.tags, old.folder);
+        INSERT INTO bookmarks_search (rowid, name, link, tags, folder)
+        VALUES (new.rowid, new.name, new.link, new.tags, new.folder);
+    END;
+";
+
+// The columns the results read, so typing a search doesn't parse every bookmark
+const LISTING_COLUMNS: &str = "
+    ALTER TABLE bookmarks ADD COLUMN private INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE bookmarks ADD COLUMN last_opened_at INTEGER;
+    ALTER TABLE bookmarks ADD COLUMN archived_at INTEGER;
+    UPDATE bookmarks SET
+        private = coalesce(json_extract(data, '$.private'), 0),
+        last_opened_at = json_extract(data, '$.last_opened_at'),
+        archived_at = json_extract(data, '$.archived_at'),
+        tags = coalesce((SELECT group_concat(value, char(10)) FROM json_each(data, '$.tags')), '');
+";
+
+pub fn open_store(dirs: &Dirs) -> Result<Box<dyn BookmarkStore>, String> {
+    let profile_dir = dirs.profile_dir();
+
+    if !profile_dir.exists() {
+        fs::create_dir_all(&profile_dir).map_err(|error| error.to_string())?;
+    }
+
+    let settings = get_settings(dirs);
+    let mut stores = get_stores(dirs, &settings);
+
+    let position = stores
+        .iter()
+        .position(|(backend, _, _)| *backend == settings.storage)
+        .unwrap();
+
+    let (backend, path, store) = stores.remove(position);
+
+    // Once the selected backend has a db there's nothing left to move
+    if !path.exists() {
+        if backend == StorageBackend::Encrypted {
+            check_unencrypted_copies(dirs)?;
+        }
+
+        for (other_path, other_store) in stores.iter().map(|(_, path, store)| (path, store)) {
+            migrate_storage(other_path, other_store.as_ref(), store.as_ref(), backend)?;
+        }
+
+        // Backups from earlier moves would keep a plain copy next to the encrypted db
+        if backend == StorageBackend::Encrypted {
+            for (_, other_path, _) in &stores {
+                let backup_path = get_backup_path(other_path);
+
+                if backup_path.exists() {
+                    fs::remove_file(backup_path).map_err(|error| error.to_string())?;
+                }
+            }
+        }
+    }
+
+    Ok(store)
+}
+
+// The offline copies and the git history are plain files that would outlive the plain db
+pub fn check_unencrypted_copies(dirs: &Dirs) -> Result<(), String> {
+    let copies = [
+        (get_archives_dir(dirs), "offline copies"),
+        (get_git_sync_dir(dirs), "git sync history"),
+    ];
+
+    for (path, name) in copies {
+        if path
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_some())
+        {
+            return Err(format!(
+                "The {name} in {} can't be encrypted, delete them to switch to the encrypted storage",
+                path.display()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn peek_db(dirs: &Dirs) -> Result<Option<BookmarksDB>, String> {
+    let settings = read_settings(dirs)?;
+    let mut stores = get_stores(dirs, &settings);
+
+    // The db is still in another backend until the selected one gets used
+    stores.sort_by_key(|(backend, _, _)| *backend != settings.storage);
+
+    let store = stores.iter().find(|(_, path, _)| path.exists());
+
+    match store {
+        Some((_, _, store)) => store.read(),
+        None => Ok(None),
+    }
+}
+
+fn get_stores(
+    dirs: &Dirs,
+    settings: &Settings,
+) -> Vec<(StorageBackend, PathBuf, Box<dyn BookmarkStore>)> {
+    vec![
+        (
+            StorageBackend::Json,
+            get_db_path(dirs),
+            Box::new(JsonStore {
+                dirs: dirs.to_owned(),
+                path: get_db_path(dirs),
+            }),
+        ),
+        (
+            StorageBackend::Sqlite,
+            get_sqlite_db_path(dirs),
+            Box::new(SqliteStore {
+                dirs: dirs.to_owned(),
+                path: get_sqlite_db_path(dirs),
+            }),
+        ),
+        (
+            StorageBackend::Encrypted,
+            get_encrypted_db_path(dirs),
+            Box::new(EncryptedStore::new(dirs, settings.encryption_key)),
+        ),
+    ]
+}
+
+fn migrate_storage(
+    from_path: &PathBuf,
+    from: &dyn BookmarkStore,
+    to: &dyn BookmarkStore,
+    to_backend: StorageBackend,
+) -> Result<(), String> {
+    if !from_path.exists() || !matches!(to.read(), Ok(None)) {
+        return Ok(());
+    }
+
+    if let Ok(Some(mut db)) = from.read() {
+        if to_backend == StorageBackend::Encrypted {
+            // The offline copies were deleted before the switch
+            for bookmark in &mut db.bookmarks {
+                bookmark.archived_at = None;
+            }
+        }
+
+        to.write(&db);
+
+        if to_backend == StorageBackend::Encrypted {
+            if to.read() != Ok(Some(db)) {
+                let _ = fs::remove_file(get_encrypted_db_path(to.dirs()));
+
+                return Err(String::from(
+                    "The encrypted db couldn't be read back, the unencrypted db was kept",
+                ));
+            }
+
+            return fs::remove_file(from_path).map_err(|error| error.to_string());
+        }
+
+        fs::rename(from_path, get_backup_path(from_path)).map_err(|error| error.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn get_backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.to_owned().into_os_string();
+    backup_path.push(".bak");
+
+    PathBuf::from(backup_path)
+}
+
+impl BookmarkStore for JsonStore {
+    fn dirs(&self) -> &Dirs {
+        &self.dirs
+    }
+
+    fn read(&self) -> Result<Option<BookmarksDB>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let db_json = fs::read_to_string(&self.path).map_err(|error| error.to_string())?;
+
+        serde_json::from_str::<BookmarksDB>(&db_json)
+            .map(Some)
+            .map_err(|error| error.to_string())
+    }
+
+    fn write(&self, db: &BookmarksDB) {
+        let json = serde_json::to_string(db).expect("Error serializing db");
+        fs::write(&self.path, &json).expect("Error writing db");
+    }
+}
+
+impl EncryptedStore {
+    pub fn new(dirs: &Dirs, key_source: KeySource) -> Self {
+        Self {
+            dirs: dirs.to_owned(),
+            path: get_encrypted_db_path(dirs),
+            key_source,
+            passphrase: std::env::var(PASSPHRASE_VAR)
+                .ok()
+                .filter(|passphrase| !passphrase.is_empty()),
+        }
+    }
+
+    pub fn rotate_key(&self, new_passphrase: Option<&str>) -> Result<(), String> {
+        let data = fs::read(&self.path).map_err(|error| error.to_string())?;
+        let plaintext = self.cipher().decrypt(&data)?;
+        let (key_version, store_id) =
+            get_key_info(&data).ok_or("The file isn't an encrypted db")?;
+
+        let cipher = match (self.key_source, new_passphrase) {
+            (KeySource::Keyring, _) => Cipher::new(KeySource::Keyring, None),
+            (KeySource::Passphrase, Some(passphrase)) => {
+                Cipher::new(KeySource::Passphrase, Some(passphrase.to_owned()))
+            }
+            (KeySource::Passphrase, None) => {
+                return Err(String::from("A new passphrase is needed"))
+            }
+        };
+
+        self.write_data(&cipher.encrypt(&plaintext, key_version + 1, store_id)?)
+    }
+
+    fn cipher(&self) -> Cipher {
+        Cipher::new(self.key_source, self.passphrase.to_owned())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<(), String> {
+        let mut temporary_path = self.path.to_owned().into_os_string();
+        temporary_path.push(".tmp");
+
+        fs::write(&temporary_path, data).map_err(|error| error.to_string())?;
+        fs::rename(&temporary_path, &self.path).map_err(|error| error.to_string())
+    }
+}
+
+impl BookmarkStore for EncryptedStore {
+    fn dirs(&self) -> &Dirs {
+        &self.dirs
+    }
+
+    fn read(&self) -> Result<Option<BookmarksDB>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&self.path).map_err(|error| error.to_string())?;
+        let plaintext = self.cipher().decrypt(&data)?;
+
+        serde_json::from_slice::<BookmarksDB>(&plaintext)
+            .map(Some)
+            .map_err(|error| error.to_string())
+    }
+
+    fn write(&self, db: &BookmarksDB) {
+        // Keeps using the key the file was last encrypted with
+        let (key_version, store_id) = fs::read(&self.path)
+            .ok()
+            .and_then(|data| get_key_info(&data))
+            .unwrap_or_else(|| (1, new_store_id()));
+
+        let json = serde_json::to_vec(db).expect("Error serializing db");
+        let data = self
+            .cipher()
+            .encrypt(&json, key_version, store_id)
+            .unwrap_or_else(|error| panic!("Error encrypting db: {error}"));
+
+        self.write_data(&data).expect("Error writing db");
+    }
+}
+
+impl SqliteStore {
+    fn open(&self) -> Connection {
+        let connection = Connection::open(&self.path).expect("Error opening sqlite db");
+
+        let schema_version =
+            Self::read_schema_version(&connection).expect("Error reading sqlite schema version");
+
+        if schema_version < SCHEMA_VERSION {
+            connection
+                .execute_batch(SCHEMA)
+                .expect("Error creating sqlite tables");
+
+            if schema_version == 1 {
+                connection
+                    .execute_batch(LISTING_COLUMNS)
+                    .expect("Error adding sqlite columns");
+            }
+
+            connection
+                .pragma_update(None, "user_version", SCHEMA_VERSION)
+                .expect("Error writing sqlite schema version");
+        }
+
+        connection
+    }
+
+    fn open_read_only(&self) -> Result<Connection, String> {
+        Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|error| error.to_string())
+    }
+
+    fn read_schema_version(connection: &Connection) -> Result<u32, String> {
+        connection
+            .query_row("PRAGMA user_version", [], |row| row.get::<_, u32>(0))
+            .map_err(|error| error.to_string())
+    }
+
+    fn read_version(connection: &Connection) -> Result<Option<u32>, String> {
+        let version = connection
+            .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .ok();
+
+        match version {
+            Some(version) => version
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("The db version {version} isn't a number")),
+            None => Ok(None),
+        }
+    }
+
+    fn read_listing(&self) -> Result<Option<BookmarksDB>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let mut connection = self.open_read_only()?;
+
+        // A file with an older schema gets the listing columns first
+        if Self::read_schema_version(&connection)? < SCHEMA_VERSION {
+            connection = self.open();
+        }
+
+        let version = match Self::read_version(&connection)? {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+
+        Ok(Some(BookmarksDB {
+            version,
+            bookmarks: read_listed_bookmarks(&connection)?,
+            groups: read_records(&connection, "groups")?,
+            ..Default::default()
+        }))
+    }
+}
+
+impl BookmarkStore for SqliteStore {
+    fn dirs(&self) -> &Dirs {
+        &self.dirs
+    }
+
+    fn read(&self) -> Result<Option<BookmarksDB>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let connection = self.open_read_only()?;
+
+        let version = match Self::read_version(&connection)? {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+
+        let mut db = BookmarksDB {
+            version,
+            bookmarks: read_records(&connection, "bookmarks")?,
+            groups: read_records(&connection, "groups")?,
+            ..Default::default()
+        };
+
+        db.tombstones = read_records(&connection, "tombstones")?;
+        db.read_later = read_records(&connection, "read_later")?;
+
+        for value in read_records::<serde_json::Value>(&connection, "trash")? {
+            if value.get("bookmark").is_some() {
+                db.trash.bookmarks.push(
+                    serde_json::from_value::<TrashedBookmark>(value)
+                        .map_err(|error| error.to_string())?,
+                );
+            } else {
+                db.trash.groups.push(
+                    serde_json::from_value::<TrashedGroup>(value)
+                        .map_err(|error| error.to_string())?,
+                );
+            }
+        }
+
+        Ok(Some(db))
+    }
+
+    fn write(&self, db: &BookmarksDB) {
+        let mut connection = self.open();
+        let transaction = connection
+            .transaction()
+            .expect("Error starting sqlite transaction");
+
+        transaction
+            .execute(
+                "INSERT INTO meta (key, value) VALUES ('version', ?1)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![db.version.to_string()],
+            )
+            .expect("Error writing db version");
+
+        let mut ids = HashSet::<String>::new();
+
+        for (position, bookmark) in db.bookmarks.iter().enumerate() {
+            let data = serde_json::to_string(bookmark).expect("Error serializing bookmark");
+
+            // Rows that didn't change aren't rewritten, which keeps the search index untouched
+            transaction
+                .execute(
+                    "INSERT INTO bookmarks (
+                        id, position, name, link, tags, folder, updated_at, data, private,
+                        last_opened_at, archived_at
+                     )
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                     ON CONFLICT (id) DO UPDATE SET
+                        position = excluded.position, name = excluded.name, link = excluded.link,
+                        tags = excluded.tags, folder = excluded.folder,
+                        updated_at = excluded.updated_at, data = excluded.data,
+                        private = excluded.private, last_opened_at = excluded.last_opened_at,
+                        archived_at = excluded.archived_at
+                     WHERE bookmarks.data IS NOT excluded.data
+                        OR bookmarks.position IS NOT excluded.position",
+                    params![
+                        bookmark.id,
+                        position,
+                        bookmark.name,
+                        bookmark.link,
+                        bookmark.tags.join("\n"),
+                        bookmark.folder.to_owned().unwrap_or_default(),
+                        bookmark.updated_at,
+                        data,
+                        bookmark.private,
+                        bookmark.last_opened_at,
+                        bookmark.archived_at
+                    ],
+                )
+                .expect("Error writing bookmark");
+
+            ids.insert(bookmark.id.to_owned());
+        }
+
+        delete_missing(&transaction, "bookmarks", &ids);
+
+        write_records(
+            &transaction,
+            "groups",
+            db.groups
+                .iter()
+                .map(|group| (group.id.to_owned(), serde_json::to_string(group)))
+                .collect(),
+        );
+
+        write_records(
+            &transaction,
+            "trash",
+            db.trash
+                .bookmarks
+                .iter()
+                .map(|trashed| {
+                    (
+                        trashed.bookmark.id.to_owned(),
+                        serde_json::to_string(trashed),
+                    )
+                })
+                .chain(
+                    db.trash.groups.iter().map(|trashed| {
+                        (trashed.group.id.to_owned(), serde_json::to_string(trashed))
+                    }),
+                )
+                .collect(),
+        );
+
+        write_records(
+            &transaction,
+            "tombstones",
+            db.tombstones
+                .iter()
+                .map(|tombstone| (tombstone.id.to_owned(), serde_json::to_string(tombstone)))
+                .collect(),
+        );
+
+        write_records(
+            &transaction,
+            "read_later",
+            db.read_later
+                .iter()
+                .map(|item| (item.id.to_owned(), serde_json::to_string(item)))
+                .collect(),
+        );
+
+        transaction.commit().expect("Error writing sqlite db");
+    }
+
+    fn get_listing(&self) -> Result<BookmarksDB, String> {
+        // A db that isn't there yet or needs a migration goes through the whole read once
+        match self.read_listing() {
+            Ok(Some(db)) if db.version >= DB_VERSION => Ok(db),
+            _ => self.load_db(),
+        }
+    }
+
+    fn search(&self, search_text: &str) -> Option<Vec<String>> {
+        // Every word is matched as a prefix, and quoted so fts syntax in the search text is ignored
+        let query = search_text
+            .split_whitespace()
+            .map(|word| word.replace('"', ""))
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{word}\"*"))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        if query.is_empty() || !self.path.exists() {
+            return None;
+        }
+
+        let connection = self.open();
+        let mut statement = connection
+            .prepare(
+                "SELECT bookmarks.id FROM bookmarks_search
+                 JOIN bookmarks ON bookmarks.rowid = bookmarks_search.rowid
+                 WHERE bookmarks_search MATCH ?1 ORDER BY rank",
+            )
+            .expect("Error preparing search");
+
+        let ids = statement
+            .query_map(params![query], |row| row.get::<_, String>(0))
+            .expect("Error searching bookmarks")
+            .filter_map(|id| id.ok())
+            .collect();
+
+        Some(ids)
+    }
+}
+
+fn read_records<T: for<'de> Deserialize<'de>>(
+    connection: &Connection,
+    table: &str,
+) -> Result<Vec<T>, String> {
+    let mut statement = connection
+        .prepare(&format!("SELECT data FROM {table} ORDER BY position"))
+        .map_err(|error| error.to_string())?;
+
+    let rows = statement
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|error| error.to_string())?;
+
+    rows.map(|data| {
+        let data = data.map_err(|error| error.to_string())?;
+        serde_json::from_str::<T>(&data).map_err(|error| error.to_string())
+    })
+    .collect()
+}
+
+fn read_listed_bookmarks(connection: &Connection) -> Result<Vec<Bookmark>, String> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, name, link, tags, folder, private, last_opened_at, archived_at
+             FROM bookmarks ORDER BY position",
+        )
+        .map_err(|error| error.to_string())?;
+
+    let rows = statement
+        .query_map([], |row| {
+            let tags = row.get::<_, String>(3)?;
+            let folder = row.get::<_, String>(4)?;
+
+            Ok(Bookmark {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                link: row.get(2)?,
+                tags: tags.lines().map(|tag| tag.to_owned()).collect(),
+                folder: Some(folder).filter(|folder| !folder.is_empty()),
+                private: row.get(5)?,
+                declared: false,
+                created_at: 0,
+                updated_at: 0,
+                last_opened_at: row.get(6)?,
+                open_count: 0,
+                archived_at: row.get(7)?,
+            })
+        })
+        .map_err(|error| error.to_string())?;
+
+    rows.map(|bookmark| bookmark.map_err(|error| error.to_string()))
+        .collect()
+}
+
+fn write_records(
+    transaction: &Transaction,
+    table: &str,
+    records: Vec<(String, serde_json::Result<String>)>,
+) {
+    let mut ids = HashSet::<String>::new();
+
+    for (position, (id, data)) in records.into_iter().enumerate() {
+        let data = data.expect("Error serializing db row");
+
+        transaction
+            .execute(
+                &format!(
+                    "INSERT INTO {table} (id, position, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (id) DO UPDATE SET position = excluded.position, data = excluded.data
+                     WHERE {table}.data IS NOT excluded.data OR {table}.position IS NOT excluded.position"
+                ),
+                params![id, position, data],
+            )
+            .expect("Error writing db row");
+
+        ids.insert(id);
+    }
+
+    delete_missing(transaction, table, &ids);
+}
+
+fn delete_missing(transaction: &Transaction, table: &str, ids: &HashSet<String>) {
+    let stored_ids = {
+        let mut statement = transaction
+            .prepare(&format!("SELECT id FROM {table}"))
+            .expect("Error preparing db read");
+
+        statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .expect("Error reading db")
+            .filter_map(|id| id.ok())
+            .collect::<Vec<String>>()
+    };
+
+    for id in stored_ids.iter().filter(|id| !ids.contains(*id)) {
+        transaction
+            .execute(&format!("DELETE FROM {table} WHERE id = ?1"), params![id])
+            .expect("Error deleting db row");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rusqlite::Connection;
+
+    use super::{open_store, peek_db, EncryptedStore, JsonStore, SqliteStore, StorageBackend};
+    use crate::{
+        bookmarks::{Bookmark, BookmarksDB, Group, DB_VERSION},
+        encryption::{get_key_info, KeySource},
+        paths::{
+            get_archive_path, get_archives_dir, get_db_path, get_encrypted_db_path,
+            get_sqlite_db_path, get_test_dirs, Dirs,
+        },
+        settings::{write_settings, Settings},
+        store::BookmarkStore,
+    };
+
+    fn sqlite_store(dirs: &Dirs, db: &BookmarksDB) -> SqliteStore {
+        let store = SqliteStore {
+            path: get_sqlite_db_path(dirs),
+            dirs: dirs.to_owned(),
+        };
+
+        store.write(db);
+        store
+    }
+
+    fn encrypted_store(dirs: &Dirs, passphrase: &str) -> EncryptedStore {
+        EncryptedStore {
+            dirs: dirs.to_owned(),
+            path: get_encrypted_db_path(dirs),
+            key_source: KeySource::Passphrase,
+            passphrase: Some(passphrase.to_owned()),
+        }
+    }
+
+    fn test_db() -> BookmarksDB {
+        let docs = Bookmark {
+            tags: vec![String::from("rust lang"), String::from("docs")],
+            folder: Some(String::from("Dev")),
+            private: true,
+            archived_at: Some(10),
+            ..Bookmark::new("Rust Docs", "https://docs.rs")
+        };
+
+        BookmarksDB {
+            version: DB_VERSION,
+            groups: vec![Group::new("Rust", &vec![docs.id.to_owned()])],
+            bookmarks: vec![
+                Bookmark::new("Crates", "https://crates.io/rust"),
+                docs,
+                Bookmark::new("Python", "https://python.org"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+
+        assert_eq!(sqlite_store(&dirs, &db).read(), Ok(Some(db)));
+    }
+
+    #[test]
+    fn lists_the_bookmarks_and_groups() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let listing = sqlite_store(&dirs, &db).get_listing().unwrap();
+
+        assert_eq!(listing.bookmarks.len(), db.bookmarks.len());
+        assert_eq!(listing.groups, db.groups);
+
+        for (listed, bookmark) in listing.bookmarks.iter().zip(&db.bookmarks) {
+            assert_eq!(listed.id, bookmark.id);
+            assert_eq!(listed.name, bookmark.name);
+            assert_eq!(listed.link, bookmark.link);
+            assert_eq!(listed.tags, bookmark.tags);
+            assert_eq!(listed.folder, bookmark.folder);
+            assert_eq!(listed.private, bookmark.private);
+            assert_eq!(listed.archived_at, bookmark.archived_at);
+        }
+    }
+
+    #[test]
+    fn adds_the_listing_columns_to_an_older_file() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let store = sqlite_store(&dirs, &db);
+
+        Connection::open(get_sqlite_db_path(&dirs))
+            .unwrap()
+            .execute_batch(
+                "ALTER TABLE bookmarks DROP COLUMN private;
+                 ALTER TABLE bookmarks DROP COLUMN last_opened_at;
+                 ALTER TABLE bookmarks DROP COLUMN archived_at;
+                 PRAGMA user_version = 1;",
+            )
+            .unwrap();
+
+        let listing = store.get_listing().unwrap();
+
+        assert!(listing.bookmarks[1].private);
+        assert_eq!(listing.bookmarks[1].tags, db.bookmarks[1].tags);
+        assert_eq!(listing.bookmarks[1].archived_at, Some(10));
+    }
+
+    #[test]
+    fn peeking_leaves_the_files_alone() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let settings = Settings {
+            storage: StorageBackend::Sqlite,
+            ..Default::default()
+        };
+
+        write_settings(&dirs, &settings);
+        JsonStore {
+            path: get_db_path(&dirs),
+            dirs: dirs.to_owned(),
+        }
+        .write(&db);
+
+        assert_eq!(peek_db(&dirs), Ok(Some(db)));
+        assert!(get_db_path(&dirs).exists());
+        assert!(!get_sqlite_db_path(&dirs).exists());
+    }
+
+    #[test]
+    fn peeking_a_damaged_db_is_an_error() {
+        let dirs = get_test_dirs();
+        fs::write(get_db_path(&dirs), "{").unwrap();
+
+        assert!(peek_db(&dirs).is_err());
+    }
+
+    #[test]
+    fn searches_names_and_links() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let hits = sqlite_store(&dirs, &db).search("rust").unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert!(hits.contains(&db.bookmarks[0].id));
+        assert!(hits.contains(&db.bookmarks[1].id));
+    }
+
+    #[test]
+    fn decrypts_what_was_encrypted() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let store = encrypted_store(&dirs, "secret");
+        store.write(&db);
+
+        let data = fs::read(get_encrypted_db_path(&dirs)).unwrap();
+
+        assert!(!String::from_utf8_lossy(&data).contains("Rust Docs"));
+        assert_eq!(store.read(), Ok(Some(db)));
+    }
+
+    #[test]
+    fn a_wrong_passphrase_is_an_error() {
+        let dirs = get_test_dirs();
+        encrypted_store(&dirs, "secret").write(&test_db());
+
+        assert!(encrypted_store(&dirs, "wrong").read().is_err());
+    }
+
+    #[test]
+    fn rotating_reencrypts_under_the_new_key() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let store = encrypted_store(&dirs, "secret");
+        store.write(&db);
+
+        let (_, store_id) = get_key_info(&fs::read(get_encrypted_db_path(&dirs)).unwrap()).unwrap();
+        store.rotate_key(Some("rotated")).unwrap();
+
+        assert!(store.read().is_err());
+        assert_eq!(encrypted_store(&dirs, "rotated").read(), Ok(Some(db)));
+        assert_eq!(
+            get_key_info(&fs::read(get_encrypted_db_path(&dirs)).unwrap()),
+            Some((2, store_id))
+        );
+    }
+
+    #[test]
+    fn refuses_other_format_versions() {
+        let dirs = get_test_dirs();
+        let store = encrypted_store(&dirs, "secret");
+        store.write(&test_db());
+
+        let path = get_encrypted_db_path(&dirs);
+        let mut data = fs::read(&path).unwrap();
+        data[4] = 2;
+        fs::write(&path, data).unwrap();
+
+        assert!(store.read().unwrap_err().contains("format 2"));
+    }
+
+    #[test]
+    fn refuses_to_encrypt_next_to_offline_copies() {
+        let dirs = get_test_dirs();
+        let db = test_db();
+        let settings = Settings {
+            storage: StorageBackend::Encrypted,
+            ..Default::default()
+        };
+        write_settings(&dirs, &settings);
+
+        let json_store = JsonStore {
+            path: get_db_path(&dirs),
+            dirs: dirs.to_owned(),
+        };
+        json_store.write(&db);
+        fs::create_dir_all(get_archives_dir(&dirs)).unwrap();
+        fs::write(
+            get_archive_path(&dirs, &db.bookmarks[1].id),
+            "<html></html>",
+        )
+        .unwrap();
+
+        assert!(open_store(&dirs).is_err());
+        assert!(get_archive_path(&dirs, &db.bookmarks[1].id).exists());
+        assert_eq!(json_store.read(), Ok(Some(db)));
+        assert!(!get_encrypted_db_path(&dirs).exists());
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+use crate::{
+    bookmarks::{now, Bookmark, BookmarksDB},
+    declarative::reconcile_declared,
+    favicons::rename_favicons,
+    git_sync::sync_with_git,
+    http_sync::sync_with_http,
+    merge::merge_dbs,
+    paths::{get_sync_lock_path, get_sync_pending_path, Dirs, DATA_DIR_VAR},
+    profiles::PROFILE_VAR,
+    settings::{get_settings, Settings},
+    storage::StorageBackend,
+};
+
+const LOCK_SECONDS: u64 = 5 * 60;
+
+pub trait BookmarkStore: Send + Sync {
+    fn dirs(&self) -> &Dirs;
+
+    fn read(&self) -> Result<Option<BookmarksDB>, String>;
+
+    fn write(&self, db: &BookmarksDB);
+
+    fn search(&self, _search_text: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    fn settings(&self) -> Settings {
+        get_settings(self.dirs())
+    }
+
+    fn get_db(&self) -> BookmarksDB {
+        self.load_db()
+            .unwrap_or_else(|error| panic!("Error reading db: {error}"))
+    }
+
+    fn load_db(&self) -> Result<BookmarksDB, String> {
+        let dirs = self.dirs();
+
+        let mut db = match self.read()? {
+            Some(db) => db,
+            None => {
+                let db = BookmarksDB::default();
+                self.write(&db);
+                db
+            }
+        };
+
+        let migrated = match db.migrate() {
+            Some(renamed_ids) => {
+                rename_favicons(dirs, &renamed_ids);
+                true
+            }
+            None => false,
+        };
+        let purged = db.purge_trash(dirs, self.settings().trash_days);
+
+        let reconciled = match reconcile_declared(dirs, &mut db) {
+            Ok(changed) => changed,
+            Err(error) => {
+                db.warnings
+                    .push(format!("Error in bookmarks.toml: {error}"));
+                false
+            }
+        };
+
+        // These changes are synced with the next write
+        if migrated || purged || reconciled {
+            self.write(&db);
+        }
+
+        Ok(db)
+    }
+
+    fn get_listing(&self) -> Result<BookmarksDB, String> {
+        self.load_db()
+    }
+
+    fn write_db(&self, db: &BookmarksDB) {
+        self.write(db);
+        spawn_sync(self.dirs());
+    }
+
+    fn get(&self, bookmark_id: &str) -> Option<Bookmark> {
+        self.get_db()
+            .bookmarks
+            .into_iter()
+            .find(|bookmark| bookmark.id == bookmark_id)
+    }
+
+    fn list(&self) -> Vec<Bookmark> {
+        self.get_db().bookmarks
+    }
+
+    fn insert(&self, bookmark: &Bookmark) {
+        let mut db = self.get_db();
+        db.bookmarks.push(bookmark.to_owned());
+
+        self.write_db(&db);
+    }
+
+    fn update(&self, bookmark: &Bookmark) -> bool {
+        let mut db = self.get_db();
+
+        match db
+            .bookmarks
+            .iter_mut()
+            .find(|existing| existing.id == bookmark.id)
+        {
+            Some(existing) => *existing = bookmark.to_owned(),
+            None => return false,
+        }
+
+        self.write_db(&db);
+
+        true
+    }
+
+    fn delete(&self, bookmark_id: &str) -> Option<Bookmark> {
+        let mut db = self.get_db();
+        let bookmark = db.trash_bookmark(bookmark_id)?;
+
+        self.write_db(&db);
+
+        Some(bookmark)
+    }
+}
+
+pub struct MemoryStore {
+    dirs: Dirs,
+    db: Mutex<Option<BookmarksDB>>,
+}
+
+impl MemoryStore {
+    pub fn new(dirs: &Dirs, db: BookmarksDB) -> Self {
+        Self {
+            dirs: dirs.to_owned(),
+            db: Mutex::new(Some(db)),
+        }
+    }
+}
+
+impl BookmarkStore for MemoryStore {
+    fn dirs(&self) -> &Dirs {
+        &self.dirs
+    }
+
+    fn read(&self) -> Result<Option<BookmarksDB>, String> {
+        Ok(self.db.lock().expect("Error locking db").to_owned())
+    }
+
+    fn write(&self, db: &BookmarksDB) {
+        *self.db.lock().expect("Error locking db") = Some(db.to_owned());
+    }
+}
+
+pub fn is_sync_enabled(settings: &Settings) -> bool {
+    settings.git_sync || !settings.http_sync_url.trim().is_empty()
+}
+
+pub fn spawn_sync(dirs: &Dirs) {
+    let settings = get_settings(dirs);
+
+    if !is_sync_enabled(&settings) || settings.storage == StorageBackend::Encrypted {
+        return;
+    }
+
+    fs::write(get_sync_pending_path(dirs), now().to_string()).expect("Error writing sync marker");
+
+    if !lock_sync(dirs) {
+        return;
+    }
+
+    let spawned = env::current_exe().and_then(|executable| {
+        Command::new(executable)
+            .args(["sync", "--pending"])
+            .env(DATA_DIR_VAR, &dirs.data_dir)
+            .env(PROFILE_VAR, &dirs.profile)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+
+    if spawned.is_err() {
+        unlock_sync(dirs);
+    }
+}
+
+pub async fn sync_pending(store: &dyn BookmarkStore) {
+    let dirs = store.dirs();
+
+    loop {
+        // The errors end up in the sync state
+        while fs::remove_file(get_sync_pending_path(dirs)).is_ok() {
+            let _ = sync_once(store).await;
+        }
+
+        unlock_sync(dirs);
+
+        // A write that came in while unlocking saw the lock and didn't start a sync
+        if !get_sync_pending_path(dirs).exists() || !lock_sync(dirs) {
+            break;
+        }
+    }
+}
+
+pub async fn sync_db(store: &dyn BookmarkStore) -> Result<bool, String> {
+    let dirs = store.dirs();
+
+    if !is_sync_enabled(&store.settings()) {
+        return Err(String::from("Neither git nor http sync is set up"));
+    }
+
+    if !lock_sync(dirs) {
+        return Err(String::from("The bookmarks are already syncing"));
+    }
+
+    let _ = fs::remove_file(get_sync_pending_path(dirs));
+    let synced = sync_once(store).await;
+
+    sync_pending(store).await;
+
+    synced
+}
+
+async fn sync_once(store: &dyn BookmarkStore) -> Result<bool, String> {
+    let dirs = store.dirs();
+
+    // Both sync backends send the db as plain json
+    if store.settings().storage == StorageBackend::Encrypted {
+        return Err(String::from(
+            "Sync is off while the storage is encrypted, it would send the bookmarks unencrypted",
+        ));
+    }
+    let local = store.get_db();
+    let mut db = local.to_owned();
+    let mut changed = false;
+
+    if let Some(merged) = sync_with_git(dirs, &db)? {
+        db = merged;
+        changed = true;
+    }
+
+    if let Some(merged) = sync_with_http(dirs, &db).await? {
+        db = merged;
+        changed = true;
+    }
+
+    if changed {
+        // Keep what was written while syncing, the next sync sends it
+        let mut current = store.read()?.unwrap_or_default();
+        current.warnings = local.warnings.to_owned();
+
+        if current != local {
+            db = merge_dbs(&local, &current, &db);
+        }
+
+        store.write(&db);
+    }
+
+    Ok(changed)
+}
+
+fn lock_sync(dirs: &Dirs) -> bool {
+    let lock_path = get_sync_lock_path(dirs);
+
+    let is_stale_lock = fs::read_to_string(&lock_path)
+        .ok()
+        .and_then(|locked_at| locked_at.trim().parse::<u64>().ok())
+        .is_some_and(|locked_at| now() >= locked_at + LOCK_SECONDS);
+
+    if is_stale_lock {
+        unlock_sync(dirs);
+    }
+
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_path)
+    {
+        Ok(_) => {
+            fs::write(&lock_path, now().to_string()).expect("Error writing sync lock");
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn unlock_sync(dirs: &Dirs) {
+    let _ = fs::remove_file(get_sync_lock_path(dirs));
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::now,
+    paths::{get_sync_state_path, Dirs},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SyncState {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub synced_hash: Option<u64>,
+    #[serde(default)]
+    pub last_synced_at: Option<u64>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub last_error_at: Option<u64>,
+}
+
+pub fn get_sync_state(dirs: &Dirs) -> SyncState {
+    let state_path = get_sync_state_path(dirs);
+
+    if !state_path.exists() {
+        return SyncState::default();
+    }
+
+    let state_json = fs::read_to_string(&state_path).expect("Error reading sync state");
+    serde_json::from_str::<SyncState>(&state_json).unwrap_or_default()
+}
+
+pub fn write_sync_state(dirs: &Dirs, state: &SyncState) {
+    let config_dir = dirs.profile_dir();
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).expect("Error creating bookmarks directory");
+    }
+
+    let json = serde_json::to_string(state).expect("Error serializing sync state");
+    fs::write(get_sync_state_path(dirs), &json).expect("Error writing sync state");
+}
+
+pub fn record_sync_success(dirs: &Dirs) {
+    let mut state = get_sync_state(dirs);
+    state.last_synced_at = Some(now());
+    state.last_error = None;
+    state.last_error_at = None;
+
+    write_sync_state(dirs, &state);
+}
+
+pub fn record_sync_error(dirs: &Dirs, error: &str) {
+    let mut state = get_sync_state(dirs);
+    state.last_error = Some(error.to_owned());
+    state.last_error_at = Some(now());
+
+    write_sync_state(dirs, &state);
+}
+
+pub fn describe_sync_state(state: &SyncState) -> String {
+    let synced = match state.last_synced_at {
+        Some(synced_at) => format!("Synced {}", describe_elapsed(synced_at)),
+        None => String::from("Never synced"),
+    };
+
+    match (&state.last_error, state.last_error_at) {
+        (Some(error), Some(error_at)) if error_at >= state.last_synced_at.unwrap_or(0) => {
+            format!("{synced}. Failed {}: {error}", describe_elapsed(error_at))
+        }
+        _ => synced,
+    }
+}
+
+pub fn describe_elapsed(timestamp: u64) -> String {
+    let elapsed = now().saturating_sub(timestamp);
+
+    match elapsed {
+        0..=59 => String::from("just now"),
+        60..=3599 => format!("{} minutes ago", elapsed / 60),
+        3600..=86399 => format!("{} hours ago", elapsed / 3600),
+        _ => format!("{} days ago", elapsed / 86400),
+    }
+}